@@ -1,16 +1,15 @@
 extern crate pocket;
 
 use pocket::Pocket;
-use std::io;
+use std::time::Duration;
 
 fn main() {
     let mut pocket = Pocket::new(&*option_env!("POCKET_CONSUMER_KEY").unwrap(), None);
-    let url = pocket.get_auth_url().unwrap();
-    println!("Follow auth URL to provide access: {}", url);
-    let _ = io::stdin().read_line(&mut String::new());
-    let username = pocket.authorize().unwrap();
+    let (username, access_token) = pocket.authorize_loopback(Duration::from_secs(300), |url| {
+        println!("Follow auth URL to provide access: {}", url);
+    }).unwrap();
     println!("username: {}", username);
-    println!("access token: {:?}", pocket.access_token());
+    println!("access token: {}", access_token);
 
     let item = pocket.push("http://example.com").unwrap();
     println!("item: {:?}", item);
@@ -0,0 +1,223 @@
+use futures::future::{self, FutureExt, TryFutureExt};
+use rustc_serialize::{json, Decodable};
+use std::sync::{Arc, Mutex};
+use url::Url;
+
+use super::{PocketResult, PocketError, IntoPocketUrl, PocketRequestToken, PocketOAuthRequest, PocketOAuthResponse,
+            PocketAuthorizeRequest, PocketAuthorizeResponse, PocketAddRequest, PocketAddResponse, PocketAddedItem,
+            PocketGetRequest, PocketGetResponse, PocketItem, PocketAction, PocketActionResult,
+            PocketSendRequest, PocketSendResponse, DEFAULT_BASE_URL, DEFAULT_AUTHORIZE_URL, DEFAULT_REDIRECT_URI};
+use async_transport::{PocketAsyncTransport, PocketFuture, ReqwestTransport};
+use decoder;
+
+// Async counterpart of `Pocket`, cheap to clone and share between tasks: clones share tokens,
+// and requests made from different tasks are sent concurrently.
+#[derive(Clone)]
+pub struct PocketAsync {
+    consumer_key: String,
+    base_url: Url,
+    authorize_url: Url,
+    auth: Arc<Mutex<PocketAsyncAuth>>,
+    transport: Arc<PocketAsyncTransport>
+}
+
+struct PocketAsyncAuth {
+    access_token: Option<String>,
+    pending: Option<PocketRequestToken>
+}
+
+impl PocketAsync {
+    pub fn new(consumer_key: &str, access_token: Option<&str>) -> PocketAsync {
+        PocketAsync::with_urls(consumer_key, access_token,
+                               Url::parse(DEFAULT_BASE_URL).unwrap(),
+                               Url::parse(DEFAULT_AUTHORIZE_URL).unwrap())
+    }
+
+    // See `Pocket::with_urls()`
+    pub fn with_urls(consumer_key: &str, access_token: Option<&str>, base_url: Url, authorize_url: Url) -> PocketAsync {
+        PocketAsync {
+            consumer_key: consumer_key.to_string(),
+            base_url: base_url,
+            authorize_url: authorize_url,
+            auth: Arc::new(Mutex::new(PocketAsyncAuth {
+                access_token: access_token.map(|v| v.to_string()),
+                pending: None
+            })),
+            transport: Arc::new(ReqwestTransport::new())
+        }
+    }
+
+    // Applies to this value and clones made after the call
+    pub fn set_transport<T: PocketAsyncTransport + 'static>(&mut self, transport: T) {
+        self.transport = Arc::new(transport);
+    }
+
+    pub fn access_token(&self) -> Option<String> {
+        self.auth.lock().unwrap().access_token.clone()
+    }
+
+    pub fn pending_token(&self) -> Option<PocketRequestToken> {
+        self.auth.lock().unwrap().pending.clone()
+    }
+
+    fn require_access_token(&self) -> PocketResult<String> {
+        self.access_token().ok_or(PocketError::NotAuthorized)
+    }
+
+    fn post(&self, method: &str, data: String) -> PocketFuture<String> {
+        match self.base_url.join(method) {
+            Ok(url) => self.transport.post(&url, data),
+            Err(e) => future::err(PocketError::InvalidUrl(e)).boxed()
+        }
+    }
+
+    fn request<Resp: Decodable + Send + 'static>(&self, method: &str, data: String) -> PocketFuture<Resp> {
+        self.post(method, data)
+            .and_then(|s| future::ready(decoder::decode::<Resp>(&*s).map_err(From::from)))
+            .boxed()
+    }
+
+    pub fn get_auth_url(&self) -> PocketFuture<Url> {
+        self.get_auth_url_with(DEFAULT_REDIRECT_URI, None)
+    }
+
+    pub fn get_auth_url_with(&self, redirect_uri: &str, state: Option<&str>) -> PocketFuture<Url> {
+        let authorize_url = self.authorize_url.clone();
+        let auth = self.auth.clone();
+        self.request_token(redirect_uri, state)
+            .map_ok(move |token| {
+                let url = token.auth_url(&authorize_url);
+                auth.lock().unwrap().pending = Some(token);
+                url
+            })
+            .boxed()
+    }
+
+    pub fn request_token(&self, redirect_uri: &str, state: Option<&str>) -> PocketFuture<PocketRequestToken> {
+        let request = match json::encode(&PocketOAuthRequest {
+            consumer_key: &*self.consumer_key,
+            redirect_uri: redirect_uri,
+            state: state
+        }) {
+            Ok(request) => request,
+            Err(e) => return future::err(From::from(e)).boxed()
+        };
+
+        let redirect_uri = redirect_uri.to_string();
+        let state = state.map(|v| v.to_string());
+        self.request("oauth/request", request)
+            .and_then(move |r: PocketOAuthResponse| future::ready(r.into_token(&*redirect_uri, state.as_ref().map(|v| &**v))))
+            .boxed()
+    }
+
+    pub fn auth_url(&self, token: &PocketRequestToken) -> Url {
+        token.auth_url(&self.authorize_url)
+    }
+
+    pub fn authorize(&self) -> PocketFuture<String> {
+        let token = match self.pending_token() {
+            Some(token) => token,
+            None => return future::err(PocketError::MissingRequestToken).boxed()
+        };
+
+        let auth = self.auth.clone();
+        self.authorize_token(&token)
+            .map_ok(move |username| {
+                let mut auth = auth.lock().unwrap();
+                if auth.pending.as_ref() == Some(&token) {
+                    auth.pending = None;
+                }
+                username
+            })
+            .boxed()
+    }
+
+    pub fn authorize_token(&self, token: &PocketRequestToken) -> PocketFuture<String> {
+        let request = match json::encode(&PocketAuthorizeRequest {
+            consumer_key: &*self.consumer_key,
+            code: &*token.code
+        }) {
+            Ok(request) => request,
+            Err(e) => return future::err(From::from(e)).boxed()
+        };
+
+        let token = token.clone();
+        let auth = self.auth.clone();
+        self.request("oauth/authorize", request)
+            .and_then(move |r: PocketAuthorizeResponse| future::ready(r.verify(&token).map(|r| {
+                auth.lock().unwrap().access_token = Some(r.access_token);
+                r.username
+            })))
+            .boxed()
+    }
+
+    pub fn add<T: IntoPocketUrl>(&self, url: T, title: Option<&str>, tags: Option<&str>, tweet_id: Option<&str>) -> PocketFuture<PocketAddedItem> {
+        let request = url.into_url().map_err(PocketError::InvalidUrl)
+            .and_then(|url| self.require_access_token().and_then(|access_token| json::encode(&PocketAddRequest {
+                consumer_key: &*self.consumer_key,
+                access_token: &*access_token,
+                url: &url,
+                title: title,
+                tags: tags,
+                tweet_id: tweet_id
+            }).map_err(From::from)));
+
+        match request {
+            Ok(request) => self.request("add", request).map_ok(|v: PocketAddResponse| v.item).boxed(),
+            Err(e) => future::err(e).boxed()
+        }
+    }
+
+    #[inline] pub fn push<T: IntoPocketUrl>(&self, url: T) -> PocketFuture<PocketAddedItem> {
+        self.add(url, None, None, None)
+    }
+
+    // Actions are encoded right away, so they don't need to outlive the returned future
+    pub fn send(&self, actions: &[&PocketAction]) -> PocketFuture<Vec<PocketActionResult>> {
+        let request = self.require_access_token().and_then(|access_token| PocketSendRequest {
+            consumer_key: &*self.consumer_key,
+            access_token: &*access_token,
+            actions: actions
+        }.to_json());
+
+        let actions: Vec<(&'static str, Option<u64>)> = actions.iter().map(|a| (a.name(), a.item_id())).collect();
+        match request {
+            Ok(request) => self.post("send", request)
+                .and_then(|s| future::ready(PocketSendResponse::from_str(&*s)))
+                .map_ok(move |r| r.into_results(actions.into_iter()))
+                .boxed(),
+            Err(e) => future::err(e).boxed()
+        }
+    }
+
+    pub fn filter<'a>(&self) -> PocketGetRequest<'a, PocketAsync> {
+        PocketGetRequest::new(self.clone())
+    }
+}
+
+impl<'a> PocketGetRequest<'a, PocketAsync> {
+    // See `PocketGetRequest::get()`
+    pub fn get(self) -> PocketFuture<Vec<PocketItem>> {
+        self.get_response()
+            .and_then(|v| future::ready(v.into_list()))
+            .boxed()
+    }
+
+    pub fn get_response(self) -> PocketFuture<PocketGetResponse> {
+        let request = self.pocket.require_access_token().and_then(|access_token| {
+            let mut request = String::new();
+            {
+                let mut encoder = json::Encoder::new(&mut request);
+                try!(self.encode_with(&*self.pocket.consumer_key, Some(&*access_token), &mut encoder));
+            }
+            Ok(request)
+        });
+
+        match request {
+            Ok(request) => self.pocket.post("get", request)
+                .and_then(|s| future::ready(PocketGetResponse::from_str(&*s)))
+                .boxed(),
+            Err(e) => future::err(e).boxed()
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use futures::future::{self, BoxFuture, Either, FutureExt, TryFutureExt};
+use reqwest::{self, Client};
+use url::Url;
+use std::io;
+
+use super::{PocketError, PocketResult};
+
+pub type PocketFuture<T> = BoxFuture<'static, PocketResult<T>>;
+
+// Async counterpart of `PocketTransport`, shared between tasks, so it takes `&self`
+pub trait PocketAsyncTransport: Send + Sync {
+    fn post(&self, url: &Url, data: String) -> PocketFuture<String>;
+}
+
+// Requests are sent with `reqwest` on top of hyper 1.x, so futures must be polled from tokio runtime
+pub struct ReqwestTransport {
+    client: Client
+}
+
+impl ReqwestTransport {
+    pub fn new() -> ReqwestTransport {
+        ReqwestTransport::with_client(Client::new())
+    }
+
+    // Use preconfigured client, e.g. with proxy or timeouts
+    pub fn with_client(client: Client) -> ReqwestTransport {
+        ReqwestTransport {
+            client: client
+        }
+    }
+}
+
+impl PocketAsyncTransport for ReqwestTransport {
+    fn post(&self, url: &Url, data: String) -> PocketFuture<String> {
+        self.client.post(&*url.to_string())
+            .header("X-Accept", "application/json")
+            .header("Content-Type", "application/json")
+            .body(data)
+            .send().map_err(http_error)
+            .and_then(|r| {
+                let code = r.headers().get("X-Error-Code").and_then(|v| v.to_str().ok()).and_then(|v| v.parse().ok());
+                match code {
+                    None => Either::Left(r.text().map_err(http_error)),
+                    Some(code) => Either::Right(future::err(PocketError::Proto(code, r.headers().get("X-Error")
+                        .and_then(|v| v.to_str().ok()).unwrap_or("unknown protocol error").to_string())))
+                }
+            })
+            .boxed()
+    }
+}
+
+fn http_error(e: reqwest::Error) -> PocketError {
+    From::from(io::Error::new(io::ErrorKind::Other, e))
+}
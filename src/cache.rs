@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use std::collections::btree_map::Values;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use rustc_serialize::json;
+use url::Url;
+
+use super::{Pocket, PocketResult, PocketItem, PocketItemStatus, PocketItemHas, PocketGetRequest,
+            PocketGetState, PocketGetTag, PocketGetType, PocketGetSort, PocketSync, PocketChanges};
+use decoder;
+use store::atomic_write;
+
+// Local copy of items, kept up to date with incremental syncs and stored in JSON lines file
+// (sync state in the first line, then an item per line). Items can be queried offline
+// with the same filters `PocketGetRequest` has.
+pub struct PocketCache {
+    path: PathBuf,
+    sync: PocketSync,
+    items: BTreeMap<u64, PocketItem>
+}
+
+impl PocketCache {
+    // Empty cache is created if there's no file yet, it's written on first `save()` or `sync()`
+    pub fn open<P: AsRef<Path>>(path: P) -> PocketResult<PocketCache> {
+        let mut cache = PocketCache {
+            path: path.as_ref().to_path_buf(),
+            sync: PocketSync::new(),
+            items: BTreeMap::new()
+        };
+
+        let file = match File::open(&cache.path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(cache),
+            Err(e) => return Err(From::from(e))
+        };
+
+        let mut lines = BufReader::new(file).lines();
+        if let Some(line) = lines.next() {
+            cache.sync = try!(decoder::decode(&*try!(line)));
+        }
+        for line in lines {
+            let line = try!(line);
+            if !line.trim().is_empty() {
+                let item: PocketItem = try!(decoder::decode(&*line));
+                cache.items.insert(item.item_id, item);
+            }
+        }
+        Ok(cache)
+    }
+
+    pub fn save(&self) -> PocketResult<()> {
+        atomic_write(&self.path, |out| {
+            try!(writeln!(out, "{}", try!(json::encode(&self.sync))));
+            for item in self.items.values() {
+                try!(writeln!(out, "{}", try!(json::encode(item))));
+            }
+            Ok(())
+        })
+    }
+
+    // Fetches changes since the last sync, applies them and saves the cache
+    pub fn sync(&mut self, pocket: &mut Pocket) -> PocketResult<PocketChanges> {
+        let changes = try!(self.sync.sync(pocket));
+        self.apply(&changes);
+        try!(self.save());
+        Ok(changes)
+    }
+
+    pub fn apply(&mut self, changes: &PocketChanges) {
+        for item in changes.added.iter().chain(changes.updated.iter()).chain(changes.archived.iter()) {
+            self.items.insert(item.item_id, item.clone());
+        }
+        for item_id in &changes.deleted {
+            self.items.remove(item_id);
+        }
+    }
+
+    pub fn sync_state(&self) -> &PocketSync {
+        &self.sync
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn item(&self, item_id: u64) -> Option<&PocketItem> {
+        self.items.get(&item_id)
+    }
+
+    pub fn items(&self) -> Values<u64, PocketItem> {
+        self.items.values()
+    }
+
+    // Items matching request filters, sorted and sliced the way server does it
+    pub fn get<'r, C>(&self, request: &PocketGetRequest<'r, C>) -> Vec<&PocketItem> {
+        let search = request.search.map(|s| s.to_lowercase());
+        let mut items: Vec<&PocketItem> = self.items.values().filter(|item| {
+            (match request.state.unwrap_or(PocketGetState::Unread) {
+                PocketGetState::Unread => item.status == PocketItemStatus::Normal,
+                PocketGetState::Archive => item.status == PocketItemStatus::Archived,
+                PocketGetState::All => item.status != PocketItemStatus::Deleted
+            }) &&
+            request.favorite.map_or(true, |fav| item.favorite == fav) &&
+            request.tag.as_ref().map_or(true, |tag| match *tag {
+                PocketGetTag::Untagged => item.tags.is_empty(),
+                PocketGetTag::Tagged(tag) => item.tags.iter().any(|t| t.tag == tag)
+            }) &&
+            request.content_type.map_or(true, |ct| match ct {
+                PocketGetType::Article => item.is_article,
+                PocketGetType::Video => has(item.has_video),
+                PocketGetType::Image => has(item.has_image)
+            }) &&
+            search.as_ref().map_or(true, |s| {
+                [&*item.given_title, &*item.resolved_title, &*item.given_url.to_string(), &*item.resolved_url.to_string()]
+                    .iter().any(|v| v.to_lowercase().contains(&**s))
+            }) &&
+            request.domain.map_or(true, |d| in_domain(&item.resolved_url, d) || in_domain(&item.given_url, d)) &&
+            request.since.map_or(true, |since| item.time_updated.map_or(false, |t| t >= since))
+        }).collect();
+
+        match request.sort.unwrap_or(PocketGetSort::Newest) {
+            PocketGetSort::Newest => items.sort_by(|a, b| (b.time_added, b.item_id).cmp(&(a.time_added, a.item_id))),
+            PocketGetSort::Oldest => items.sort_by(|a, b| (a.time_added, a.item_id).cmp(&(b.time_added, b.item_id))),
+            PocketGetSort::Title => items.sort_by(|a, b| title(a).cmp(title(b))),
+            PocketGetSort::Site => items.sort_by(|a, b| a.resolved_url.to_string().cmp(&b.resolved_url.to_string()))
+        }
+
+        let count = request.count.unwrap_or(items.len());
+        items.into_iter().skip(request.offset.unwrap_or(0)).take(count).collect()
+    }
+}
+
+fn has(v: PocketItemHas) -> bool {
+    v == PocketItemHas::Yes || v == PocketItemHas::Is
+}
+
+fn title(item: &PocketItem) -> &str {
+    if item.resolved_title.is_empty() { &*item.given_title } else { &*item.resolved_title }
+}
+
+fn in_domain(url: &Url, domain: &str) -> bool {
+    url.domain().map_or(false, |d| d == domain || d.ends_with(&*format!(".{}", domain)))
+}
@@ -0,0 +1,351 @@
+use rustc_serialize::{Decodable, Decoder};
+use rustc_serialize::json::{Json, DecoderError};
+use rustc_serialize::json::DecoderError::{ExpectedError, MissingFieldError, UnknownVariantError, ApplicationError};
+
+pub type DecodeResult<T> = Result<T, DecoderError>;
+
+// JSON decoder tolerant to the way Pocket encodes values: numbers and booleans may come
+// as strings (empty string meaning zero, false or no value), booleans as 0/1,
+// and lists as objects with index keys (or empty arrays in place of empty objects).
+// Otherwise works just like `json::Decoder`.
+pub struct PocketDecoder {
+    stack: Vec<Json>,
+    path: Vec<String>,
+    map_key: Option<String>,
+    error_path: Option<String>
+}
+
+impl PocketDecoder {
+    pub fn new(json: Json) -> PocketDecoder {
+        PocketDecoder {
+            stack: vec![json],
+            path: Vec::new(),
+            map_key: None,
+            error_path: None
+        }
+    }
+
+    // Dot separated path to the value decoding failed at, e.g. "images.1.src"
+    pub fn error_path(&self) -> Option<&str> {
+        self.error_path.as_ref().map(|v| &**v)
+    }
+
+    fn pop(&mut self) -> Json {
+        self.stack.pop().unwrap()
+    }
+
+    fn nested<T, F>(&mut self, name: String, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        self.path.push(name);
+        let result = f(self);
+        if result.is_err() && self.error_path.is_none() {
+            self.error_path = Some(self.path.join("."));
+        }
+        self.path.pop();
+        result
+    }
+
+    fn read_number(&mut self) -> DecodeResult<Json> {
+        match self.pop() {
+            v @ Json::I64(_) | v @ Json::U64(_) | v @ Json::F64(_) => Ok(v),
+            Json::Boolean(b) => Ok(Json::U64(b as u64)),
+            Json::String(s) => {
+                let s = s.trim();
+                if s.is_empty() {
+                    Ok(Json::U64(0))
+                } else if let Ok(v) = s.parse() {
+                    Ok(Json::U64(v))
+                } else if let Ok(v) = s.parse() {
+                    Ok(Json::I64(v))
+                } else if let Ok(v) = s.parse() {
+                    Ok(Json::F64(v))
+                } else {
+                    Err(ExpectedError("Number".to_string(), s.to_string()))
+                }
+            },
+            other => Err(ExpectedError("Number".to_string(), other.to_string()))
+        }
+    }
+
+    fn read_unsigned(&mut self, max: u64) -> DecodeResult<u64> {
+        let number = try!(self.read_number());
+        let value = match number {
+            Json::U64(v) => Some(v),
+            Json::I64(v) if v >= 0 => Some(v as u64),
+            // casts saturate, so out of range floats are checked before
+            Json::F64(v) if v >= 0.0 && v.fract() == 0.0 && v < u64::max_value() as f64 => Some(v as u64),
+            _ => None
+        };
+        match value {
+            Some(v) if v <= max => Ok(v),
+            _ => Err(ExpectedError("Unsigned integer".to_string(), number.to_string()))
+        }
+    }
+
+    fn read_signed(&mut self, min: i64, max: i64) -> DecodeResult<i64> {
+        let number = try!(self.read_number());
+        let value = match number {
+            Json::I64(v) => Some(v),
+            Json::U64(v) if v <= i64::max_value() as u64 => Some(v as i64),
+            Json::F64(v) if v.fract() == 0.0 && v >= i64::min_value() as f64 && v < i64::max_value() as f64 => Some(v as i64),
+            _ => None
+        };
+        match value {
+            Some(v) if v >= min && v <= max => Ok(v),
+            _ => Err(ExpectedError("Integer".to_string(), number.to_string()))
+        }
+    }
+}
+
+impl Decoder for PocketDecoder {
+    type Error = DecoderError;
+
+    fn read_nil(&mut self) -> DecodeResult<()> {
+        match self.pop() {
+            Json::Null => Ok(()),
+            other => Err(ExpectedError("Null".to_string(), other.to_string()))
+        }
+    }
+
+    fn read_usize(&mut self) -> DecodeResult<usize> { self.read_unsigned(usize::max_value() as u64).map(|v| v as usize) }
+    fn read_u64(&mut self) -> DecodeResult<u64> { self.read_unsigned(u64::max_value()) }
+    fn read_u32(&mut self) -> DecodeResult<u32> { self.read_unsigned(u32::max_value() as u64).map(|v| v as u32) }
+    fn read_u16(&mut self) -> DecodeResult<u16> { self.read_unsigned(u16::max_value() as u64).map(|v| v as u16) }
+    fn read_u8(&mut self) -> DecodeResult<u8> { self.read_unsigned(u8::max_value() as u64).map(|v| v as u8) }
+
+    fn read_isize(&mut self) -> DecodeResult<isize> { self.read_signed(isize::min_value() as i64, isize::max_value() as i64).map(|v| v as isize) }
+    fn read_i64(&mut self) -> DecodeResult<i64> { self.read_signed(i64::min_value(), i64::max_value()) }
+    fn read_i32(&mut self) -> DecodeResult<i32> { self.read_signed(i32::min_value() as i64, i32::max_value() as i64).map(|v| v as i32) }
+    fn read_i16(&mut self) -> DecodeResult<i16> { self.read_signed(i16::min_value() as i64, i16::max_value() as i64).map(|v| v as i16) }
+    fn read_i8(&mut self) -> DecodeResult<i8> { self.read_signed(i8::min_value() as i64, i8::max_value() as i64).map(|v| v as i8) }
+
+    fn read_bool(&mut self) -> DecodeResult<bool> {
+        match self.pop() {
+            Json::Boolean(b) => Ok(b),
+            Json::String(ref s) if s == "true" => Ok(true),
+            Json::String(ref s) if s == "false" => Ok(false),
+            other => {
+                self.stack.push(other);
+                self.read_f64().map(|v| v != 0.0)
+            }
+        }
+    }
+
+    fn read_f64(&mut self) -> DecodeResult<f64> {
+        match try!(self.read_number()) {
+            Json::I64(v) => Ok(v as f64),
+            Json::U64(v) => Ok(v as f64),
+            Json::F64(v) => Ok(v),
+            other => Err(ExpectedError("Number".to_string(), other.to_string()))
+        }
+    }
+
+    fn read_f32(&mut self) -> DecodeResult<f32> {
+        self.read_f64().map(|v| v as f32)
+    }
+
+    fn read_char(&mut self) -> DecodeResult<char> {
+        let s = try!(self.read_str());
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(ExpectedError("single character string".to_string(), s.clone()))
+        }
+    }
+
+    fn read_str(&mut self) -> DecodeResult<String> {
+        match self.pop() {
+            Json::String(s) => Ok(s),
+            Json::I64(v) => Ok(v.to_string()),
+            Json::U64(v) => Ok(v.to_string()),
+            Json::F64(v) => Ok(v.to_string()),
+            Json::Boolean(b) => Ok(b.to_string()),
+            other => Err(ExpectedError("String".to_string(), other.to_string()))
+        }
+    }
+
+    fn read_enum<T, F>(&mut self, _name: &str, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        f(self)
+    }
+
+    fn read_enum_variant<T, F>(&mut self, names: &[&str], mut f: F) -> DecodeResult<T>
+        where F: FnMut(&mut PocketDecoder, usize) -> DecodeResult<T> {
+        let name = match self.pop() {
+            Json::String(s) => s,
+            Json::Object(mut obj) => {
+                let name = match obj.remove("variant") {
+                    Some(Json::String(s)) => s,
+                    Some(other) => return Err(ExpectedError("String".to_string(), other.to_string())),
+                    None => return Err(MissingFieldError("variant".to_string()))
+                };
+                match obj.remove("fields") {
+                    Some(Json::Array(fields)) => self.stack.extend(fields.into_iter().rev()),
+                    Some(other) => return Err(ExpectedError("Array".to_string(), other.to_string())),
+                    None => return Err(MissingFieldError("fields".to_string()))
+                }
+                name
+            },
+            other => return Err(ExpectedError("String or Object".to_string(), other.to_string()))
+        };
+
+        match names.iter().position(|n| *n == &*name) {
+            Some(idx) => f(self, idx),
+            None => Err(UnknownVariantError(name))
+        }
+    }
+
+    fn read_enum_variant_arg<T, F>(&mut self, _idx: usize, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        f(self)
+    }
+
+    fn read_enum_struct_variant<T, F>(&mut self, names: &[&str], f: F) -> DecodeResult<T>
+        where F: FnMut(&mut PocketDecoder, usize) -> DecodeResult<T> {
+        self.read_enum_variant(names, f)
+    }
+
+    fn read_enum_struct_variant_field<T, F>(&mut self, _name: &str, idx: usize, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        self.read_enum_variant_arg(idx, f)
+    }
+
+    fn read_struct<T, F>(&mut self, _name: &str, _len: usize, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        let value = try!(f(self));
+        self.pop();
+        Ok(value)
+    }
+
+    fn read_struct_field<T, F>(&mut self, name: &str, _idx: usize, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        let mut obj = match self.pop() {
+            Json::Object(obj) => obj,
+            other => return Err(ExpectedError("Object".to_string(), other.to_string()))
+        };
+
+        let value = match obj.remove(name) {
+            None => {
+                self.stack.push(Json::Null);
+                match self.nested(name.to_string(), f) {
+                    Ok(v) => v,
+                    Err(_) => return Err(MissingFieldError(name.to_string()))
+                }
+            },
+            Some(json) => {
+                self.stack.push(json);
+                try!(self.nested(name.to_string(), f))
+            }
+        };
+        self.stack.push(Json::Object(obj));
+        Ok(value)
+    }
+
+    fn read_tuple<T, F>(&mut self, len: usize, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        self.read_seq(move |d, n| if n == len {
+            f(d)
+        } else {
+            Err(ExpectedError(format!("Tuple{}", len), format!("Tuple{}", n)))
+        })
+    }
+
+    fn read_tuple_arg<T, F>(&mut self, idx: usize, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        self.read_seq_elt(idx, f)
+    }
+
+    fn read_tuple_struct<T, F>(&mut self, _name: &str, len: usize, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        self.read_tuple(len, f)
+    }
+
+    fn read_tuple_struct_arg<T, F>(&mut self, idx: usize, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        self.read_tuple_arg(idx, f)
+    }
+
+    // Empty string is no value as well as null
+    fn read_option<T, F>(&mut self, mut f: F) -> DecodeResult<T>
+        where F: FnMut(&mut PocketDecoder, bool) -> DecodeResult<T> {
+        match self.pop() {
+            Json::Null => f(self, false),
+            Json::String(ref s) if s.is_empty() => f(self, false),
+            value => {
+                self.stack.push(value);
+                f(self, true)
+            }
+        }
+    }
+
+    // Objects with index keys are read in order of their keys
+    fn read_seq<T, F>(&mut self, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder, usize) -> DecodeResult<T> {
+        let items = match self.pop() {
+            Json::Array(items) => items,
+            Json::Object(obj) => {
+                let mut entries: Vec<(String, Json)> = obj.into_iter().collect();
+                entries.sort_by(|a, b| (a.0.parse::<u64>().ok(), &a.0).cmp(&(b.0.parse::<u64>().ok(), &b.0)));
+                entries.into_iter().map(|(_, v)| v).collect()
+            },
+            Json::Null => Vec::new(),
+            other => return Err(ExpectedError("Array".to_string(), other.to_string()))
+        };
+
+        let len = items.len();
+        self.stack.extend(items.into_iter().rev());
+        f(self, len)
+    }
+
+    fn read_seq_elt<T, F>(&mut self, idx: usize, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        self.nested(idx.to_string(), f)
+    }
+
+    // Arrays are read as objects with index keys
+    fn read_map<T, F>(&mut self, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder, usize) -> DecodeResult<T> {
+        let entries: Vec<(String, Json)> = match self.pop() {
+            Json::Object(obj) => obj.into_iter().collect(),
+            Json::Array(items) => items.into_iter().enumerate().map(|(i, v)| (i.to_string(), v)).collect(),
+            Json::Null => Vec::new(),
+            other => return Err(ExpectedError("Object".to_string(), other.to_string()))
+        };
+
+        let len = entries.len();
+        for (key, value) in entries.into_iter().rev() {
+            self.stack.push(value);
+            self.stack.push(Json::String(key));
+        }
+        f(self, len)
+    }
+
+    fn read_map_elt_key<T, F>(&mut self, _idx: usize, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        self.map_key = match self.stack.last() {
+            Some(&Json::String(ref key)) => Some(key.clone()),
+            _ => None
+        };
+        f(self)
+    }
+
+    fn read_map_elt_val<T, F>(&mut self, idx: usize, f: F) -> DecodeResult<T>
+        where F: FnOnce(&mut PocketDecoder) -> DecodeResult<T> {
+        let key = self.map_key.take().unwrap_or_else(|| idx.to_string());
+        self.nested(key, f)
+    }
+
+    fn error(&mut self, err: &str) -> DecoderError {
+        ApplicationError(err.to_string())
+    }
+}
+
+// Same as `json::decode`, but using `PocketDecoder`
+pub fn decode<T: Decodable>(s: &str) -> DecodeResult<T> {
+    let json = try!(Json::from_str(s).map_err(DecoderError::ParseError));
+    decode_json(json)
+}
+
+pub fn decode_json<T: Decodable>(json: Json) -> DecodeResult<T> {
+    Decodable::decode(&mut PocketDecoder::new(json))
+}
@@ -0,0 +1,116 @@
+use hyper::client::Client;
+use hyper::header::{ContentType, Header, HeaderFormat};
+use hyper::header::parsing::from_one_raw_str;
+use hyper::error::Error as HttpError;
+use url::Url;
+use mime::Mime;
+use std::{fmt, ops};
+use std::io::Read;
+
+use super::{PocketError, PocketResult};
+use transport::PocketTransport;
+
+pub struct HyperTransport {
+    client: Client
+}
+
+impl HyperTransport {
+    pub fn new() -> HyperTransport {
+        HyperTransport::with_client(Client::new())
+    }
+
+    // Use preconfigured client, e.g. with proxy or custom TLS settings
+    pub fn with_client(client: Client) -> HyperTransport {
+        HyperTransport {
+            client: client
+        }
+    }
+}
+
+impl PocketTransport for HyperTransport {
+    fn post(&mut self, url: &Url, data: &str) -> PocketResult<String> {
+        let app_json: Mime = "application/json".parse().unwrap();
+        self.client.post(url.clone())
+            .header(XAccept(app_json.clone()))
+            .header(ContentType(app_json.clone()))
+            .body(data)
+            .send().map_err(From::from)
+            .and_then(|mut r| match r.headers.get::<XErrorCode>().map(|v| v.0) {
+                None => {
+                    let mut out = String::new();
+                    r.read_to_string(&mut out).map_err(From::from).map(|_| out)
+                },
+                Some(code) => Err(PocketError::Proto(code, r.headers.get::<XError>().map(|v| &*v.0)
+                                                     .unwrap_or("unknown protocol error").to_string())),
+            })
+    }
+}
+
+#[derive(Clone, Debug)]
+struct XAccept(pub Mime);
+
+impl ops::Deref for XAccept {
+    type Target = Mime;
+    fn deref<'a>(&'a self) -> &'a Mime {
+        &self.0
+    }
+}
+
+impl ops::DerefMut for XAccept {
+    fn deref_mut<'a>(&'a mut self) -> &'a mut Mime {
+        &mut self.0
+    }
+}
+
+impl Header for XAccept {
+    fn header_name() -> &'static str {
+        "X-Accept"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Result<XAccept, HttpError> {
+        from_one_raw_str(raw).map(|mime| XAccept(mime))
+    }
+}
+
+impl HeaderFormat for XAccept {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, fmt)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct XError(String);
+#[derive(Clone, Debug)]
+struct XErrorCode(u16);
+
+impl Header for XError {
+    fn header_name() -> &'static str {
+        "X-Error"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Result<XError, HttpError> {
+        from_one_raw_str(raw).map(|error| XError(error))
+    }
+}
+
+impl HeaderFormat for XError {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, fmt)
+    }
+}
+
+impl Header for XErrorCode {
+    fn header_name() -> &'static str {
+        "X-Error-Code"
+    }
+
+    fn parse_header(raw: &[Vec<u8>]) -> Result<XErrorCode, HttpError> {
+        from_one_raw_str(raw).map(|code| XErrorCode(code))
+    }
+}
+
+impl HeaderFormat for XErrorCode {
+    fn fmt_header(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, fmt)
+    }
+}
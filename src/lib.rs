@@ -1,27 +1,57 @@
-extern crate hyper;
+#[cfg(feature = "hyper")] extern crate hyper;
 extern crate rustc_serialize;
 extern crate url;
 extern crate mime;
 extern crate time;
+extern crate getrandom;
+#[cfg(feature = "async")] extern crate reqwest;
+#[cfg(feature = "async")] extern crate futures;
 
 #[cfg(test)] #[macro_use] extern crate log;
 
-use hyper::header::{Header, HeaderFormat, ContentType};
-use hyper::client::{Client, IntoUrl};
-use hyper::net::HttpConnector;
-use hyper::header::parsing::from_one_raw_str;
+mod transport;
+#[cfg(feature = "hyper")]
+mod hyper_transport;
+mod loopback;
+mod decoder;
+mod sync;
+mod cache;
+mod queue;
+mod store;
+#[cfg(feature = "async")]
+mod async_transport;
+#[cfg(feature = "async")]
+mod async_client;
+#[cfg(feature = "mock")]
+pub mod mock;
+
+pub use transport::{PocketTransport, RecordingTransport, ReplayTransport, PocketExchange};
+#[cfg(feature = "hyper")]
+pub use hyper_transport::HyperTransport;
+pub use loopback::LoopbackListener;
+pub use decoder::PocketDecoder;
+pub use sync::{PocketSync, PocketChanges};
+pub use cache::PocketCache;
+pub use queue::PocketActionQueue;
+#[cfg(feature = "async")]
+pub use async_transport::{PocketAsyncTransport, ReqwestTransport, PocketFuture};
+#[cfg(feature = "async")]
+pub use async_client::PocketAsync;
+
+#[cfg(feature = "hyper")]
 use hyper::error::Error as HttpError;
-use url::Url;
+use url::{Url, ParseError as UrlError};
 use mime::Mime;
 use rustc_serialize::{json, Decodable, Encodable, Decoder, Encoder};
 use rustc_serialize::json::{ToJson, Json};
 use std::error::Error;
 use std::convert::{From, Into};
 use std::io::Error as IoError;
-use std::io::Read;
 use std::collections::BTreeMap;
 use std::result::Result;
 use time::Timespec;
+use std::time::Duration;
+use decoder::decode_json;
 
 pub trait JsonEncodable {
     fn json_encode(&self, e: &mut json::Encoder) -> Result<(), json::EncoderError>;
@@ -29,6 +59,7 @@ pub trait JsonEncodable {
 
 pub trait PocketAction : JsonEncodable {
     fn name(&self) -> &'static str;
+    fn item_id(&self) -> Option<u64>;
 }
 
 impl<T: Encodable> JsonEncodable for T {
@@ -44,8 +75,23 @@ macro_rules! impl_item_pocket_action {
             time: Option<u64>
         }
 
+        impl $cls {
+            pub fn new(item_id: u64) -> $cls {
+                $cls {
+                    item_id: item_id,
+                    time: None
+                }
+            }
+
+            pub fn at(mut self, time: Timespec) -> $cls {
+                self.time = Some(time.sec as u64);
+                self
+            }
+        }
+
         impl PocketAction for $cls {
             fn name(&self) -> &'static str { $name }
+            fn item_id(&self) -> Option<u64> { Some(self.item_id) }
         }
 
         impl JsonEncodable for $cls {
@@ -65,10 +111,17 @@ macro_rules! impl_item_pocket_action {
 
 #[derive(Debug)]
 pub enum PocketError {
+    #[cfg(feature = "hyper")]
     Http(HttpError),
+    Io(IoError),
     Json(json::DecoderError),
     Format(json::EncoderError),
-    Proto(u16, String)
+    Proto(u16, String),
+    StateMismatch,
+    MissingRequestToken,
+    NotAuthorized,
+    InvalidUrl(UrlError),
+    Decode(PocketDecodeError)
 }
 
 pub type PocketResult<T> = Result<T, PocketError>;
@@ -85,12 +138,19 @@ impl From<json::DecoderError> for PocketError {
     }
 }
 
+impl From<json::ParserError> for PocketError {
+    fn from(err: json::ParserError) -> PocketError {
+        PocketError::Json(json::DecoderError::ParseError(err))
+    }
+}
+
 impl From<IoError> for PocketError {
     fn from(err: IoError) -> PocketError {
-        PocketError::Http(From::from(err))
+        PocketError::Io(err)
     }
 }
 
+#[cfg(feature = "hyper")]
 impl From<HttpError> for PocketError {
     fn from(err: HttpError) -> PocketError {
         PocketError::Http(err)
@@ -100,19 +160,33 @@ impl From<HttpError> for PocketError {
 impl Error for PocketError {
     fn description(&self) -> &str {
         match *self {
+            #[cfg(feature = "hyper")]
             PocketError::Http(ref e) => e.description(),
+            PocketError::Io(ref e) => e.description(),
             PocketError::Json(ref e) => e.description(),
             PocketError::Format(ref e) => e.description(),
-            PocketError::Proto(..) => "protocol error"
+            PocketError::Proto(..) => "protocol error",
+            PocketError::StateMismatch => "OAuth state mismatch",
+            PocketError::MissingRequestToken => "no pending OAuth request token",
+            PocketError::NotAuthorized => "access token required",
+            PocketError::InvalidUrl(ref e) => e.description(),
+            PocketError::Decode(ref e) => e.error.description()
         }
     }
 
     fn cause(&self) -> Option<&Error> {
         match *self {
+            #[cfg(feature = "hyper")]
             PocketError::Http(ref e) => Some(e),
+            PocketError::Io(ref e) => Some(e),
             PocketError::Json(ref e) => Some(e),
             PocketError::Format(ref e) => Some(e),
-            PocketError::Proto(..) => None
+            PocketError::Proto(..) => None,
+            PocketError::StateMismatch => None,
+            PocketError::MissingRequestToken => None,
+            PocketError::NotAuthorized => None,
+            PocketError::InvalidUrl(ref e) => Some(e),
+            PocketError::Decode(ref e) => Some(&e.error)
         }
     }
 }
@@ -120,88 +194,31 @@ impl Error for PocketError {
 impl std::fmt::Display for PocketError {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
         match *self {
+            #[cfg(feature = "hyper")]
             PocketError::Http(ref e) => e.fmt(fmt),
+            PocketError::Io(ref e) => e.fmt(fmt),
             PocketError::Json(ref e) => e.fmt(fmt),
             PocketError::Format(ref e) => e.fmt(fmt),
-            PocketError::Proto(ref code, ref msg) => fmt.write_str(&*format!("{} (code {})", msg, code))
+            PocketError::Proto(ref code, ref msg) => fmt.write_str(&*format!("{} (code {})", msg, code)),
+            PocketError::InvalidUrl(ref e) => e.fmt(fmt),
+            PocketError::Decode(ref e) => fmt.write_str(&*format!("item {}, field {}: {}", e.item_id,
+                                                                  e.field.as_ref().map(|v| &**v).unwrap_or("?"), e.error)),
+            PocketError::StateMismatch | PocketError::MissingRequestToken | PocketError::NotAuthorized => fmt.write_str(self.description())
         }
     }
 }
 
-#[derive(Clone, Debug)]
-struct XAccept(pub Mime);
-
-impl std::ops::Deref for XAccept {
-    type Target = Mime;
-    fn deref<'a>(&'a self) -> &'a Mime {
-        &self.0
-    }
-}
-
-impl std::ops::DerefMut for XAccept {
-    fn deref_mut<'a>(&'a mut self) -> &'a mut Mime {
-        &mut self.0
-    }
-}
-
-impl Header for XAccept {
-    fn header_name() -> &'static str {
-        "X-Accept"
-    }
-
-    fn parse_header(raw: &[Vec<u8>]) -> Result<XAccept, HttpError> {
-        from_one_raw_str(raw).map(|mime| XAccept(mime))
-    }
-}
-
-impl HeaderFormat for XAccept {
-    fn fmt_header(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        std::fmt::Display::fmt(&self.0, fmt)
-    }
-}
-
-#[derive(Clone, Debug)]
-struct XError(String);
-#[derive(Clone, Debug)]
-struct XErrorCode(u16);
-
-impl Header for XError {
-    fn header_name() -> &'static str {
-        "X-Error"
-    }
-
-    fn parse_header(raw: &[Vec<u8>]) -> Result<XError, HttpError> {
-        from_one_raw_str(raw).map(|error| XError(error))
-    }
-}
-
-impl HeaderFormat for XError {
-    fn fmt_header(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        std::fmt::Display::fmt(&self.0, fmt)
-    }
-}
-
-impl Header for XErrorCode {
-    fn header_name() -> &'static str {
-        "X-Error-Code"
-    }
-
-    fn parse_header(raw: &[Vec<u8>]) -> Result<XErrorCode, HttpError> {
-        from_one_raw_str(raw).map(|code| XErrorCode(code))
-    }
-}
-
-impl HeaderFormat for XErrorCode {
-    fn fmt_header(&self, fmt: &mut std::fmt::Formatter) -> std::fmt::Result {
-        std::fmt::Display::fmt(&self.0, fmt)
-    }
-}
+pub const DEFAULT_BASE_URL: &'static str = "https://getpocket.com/v3/";
+pub const DEFAULT_AUTHORIZE_URL: &'static str = "https://getpocket.com/auth/authorize";
+const DEFAULT_REDIRECT_URI: &'static str = "rustapi:finishauth";
 
 pub struct Pocket {
     consumer_key: String,
     access_token: Option<String>,
-    code: Option<String>,
-    client: Client
+    pending: Option<PocketRequestToken>,
+    base_url: Url,
+    authorize_url: Url,
+    transport: Box<PocketTransport + Send>
 }
 
 #[derive(RustcEncodable)]
@@ -211,10 +228,42 @@ pub struct PocketOAuthRequest<'a> {
     state: Option<&'a str>
 }
 
-#[derive(RustcDecodable)]
+#[derive(RustcDecodable, Debug)]
 pub struct PocketOAuthResponse {
-    code: String,
-    state: Option<String>
+    pub code: String,
+    pub state: Option<String>
+}
+
+// OAuth request token waiting for user to confirm access,
+// can be stored and used to authorize later, e.g. when handling redirect in another process
+#[derive(RustcEncodable, RustcDecodable, Debug, Clone, PartialEq)]
+pub struct PocketRequestToken {
+    pub code: String,
+    pub redirect_uri: String,
+    pub state: Option<String>
+}
+
+impl PocketOAuthResponse {
+    // Missing state is a mismatch too, if one was sent
+    fn into_token(self, redirect_uri: &str, state: Option<&str>) -> PocketResult<PocketRequestToken> {
+        if self.state.as_ref().map(|v| &**v) != state {
+            return Err(PocketError::StateMismatch);
+        }
+
+        Ok(PocketRequestToken {
+            code: self.code,
+            redirect_uri: redirect_uri.to_string(),
+            state: self.state
+        })
+    }
+}
+
+impl PocketRequestToken {
+    fn auth_url(&self, authorize_url: &Url) -> Url {
+        let mut url = authorize_url.clone();
+        url.set_query_from_pairs(vec![("request_token", &*self.code), ("redirect_uri", &*self.redirect_uri)].into_iter());
+        url
+    }
 }
 
 #[derive(RustcEncodable)]
@@ -223,10 +272,21 @@ pub struct PocketAuthorizeRequest<'a> {
     code: &'a str
 }
 
-#[derive(RustcDecodable)]
+#[derive(RustcDecodable, Debug)]
 pub struct PocketAuthorizeResponse {
-    access_token: String,
-    username: String
+    pub access_token: String,
+    pub username: String,
+    pub state: Option<String>
+}
+
+impl PocketAuthorizeResponse {
+    // State is verified on request step, here it's checked only if Pocket returned it
+    fn verify(self, token: &PocketRequestToken) -> PocketResult<PocketAuthorizeResponse> {
+        if self.state.is_some() && self.state != token.state {
+            return Err(PocketError::StateMismatch);
+        }
+        Ok(self)
+    }
 }
 
 #[derive(RustcEncodable)]
@@ -240,7 +300,7 @@ pub struct PocketAddRequest<'a> {
 }
 
 
-#[derive(RustcDecodable, Debug, PartialEq)]
+#[derive(RustcEncodable, RustcDecodable, Debug, PartialEq, Clone)]
 pub struct ItemImage {
     pub item_id: u64, // String
     pub image_id: u64, // String
@@ -251,7 +311,7 @@ pub struct ItemImage {
     pub credit: String,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct ItemVideo {
     pub item_id: u64, // String
     pub video_id: u64, // String
@@ -278,24 +338,114 @@ impl Decodable for ItemVideo {
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub enum PocketItemHas {
-    No = 0,
-    Yes = 1,
-    Is = 2
+impl Encodable for ItemVideo {
+    fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
+        e.emit_struct("ItemVideo", 8, |e| {
+            e.emit_struct_field("item_id", 0, |e| self.item_id.encode(e)).and_then(|_|
+            e.emit_struct_field("video_id", 1, |e| self.video_id.encode(e))).and_then(|_|
+            e.emit_struct_field("src", 2, |e| self.src.encode(e))).and_then(|_|
+            e.emit_struct_field("width", 3, |e| self.width.encode(e))).and_then(|_|
+            e.emit_struct_field("height", 4, |e| self.height.encode(e))).and_then(|_|
+            e.emit_struct_field("length", 5, |e| self.length.encode(e))).and_then(|_|
+            e.emit_struct_field("vid", 6, |e| self.vid.encode(e))).and_then(|_|
+            e.emit_struct_field("type", 7, |e| self.vtype.encode(e)))
+        })
+    }
+}
+
+#[derive(RustcEncodable, RustcDecodable, Debug, PartialEq, Clone)]
+pub struct ItemTag {
+    pub item_id: u64, // String
+    pub tag: String,
 }
 
-impl Decodable for PocketItemHas {
-    fn decode<D: Decoder>(d: &mut D) -> Result<PocketItemHas, D::Error> {
-        d.read_u8().map(|v| match v {
-            0 => PocketItemHas::No,
-            1 => PocketItemHas::Yes,
-            2 => PocketItemHas::Is,
-            _ => unreachable!()
+#[derive(RustcEncodable, RustcDecodable, Debug, PartialEq, Clone)]
+pub struct ItemAuthor {
+    pub item_id: u64, // String
+    pub author_id: u64, // String
+    pub name: String,
+    pub url: String,
+}
+
+#[derive(RustcEncodable, RustcDecodable, Debug, PartialEq, Clone)]
+pub struct ItemDomainMetadata {
+    pub name: Option<String>,
+    pub logo: Option<Url>,
+    pub greyscale_logo: Option<Url>,
+}
+
+// Dates are formatted as "YYYY-MM-DD HH:MM:SS" in UTC, "0000-00-00 00:00:00" means no date
+fn read_date<D: Decoder>(d: &mut D) -> Result<Option<Timespec>, D::Error> {
+    d.read_option(|d, b| if b {
+        d.read_str().and_then(|v| match &*v {
+            "0000-00-00 00:00:00" => Ok(None),
+            _ => time::strptime(&*v, "%Y-%m-%d %H:%M:%S")
+                .map(|tm| Some(tm.to_timespec()))
+                .map_err(|_| d.error(&*format!("invalid date: {}", v)))
         })
+    } else {
+        Ok(None)
+    })
+}
+
+// Unix timestamp, 0 means the event never happened
+fn read_timestamp<D: Decoder>(d: &mut D) -> Result<Option<Timespec>, D::Error> {
+    d.read_i64().map(|v| match v {
+        0 => None,
+        v => Some(Timespec::new(v, 0))
+    })
+}
+
+// Tags, authors, images and videos come as objects keyed by tag name or id
+fn read_keyed_list<D: Decoder, T: Decodable>(d: &mut D) -> Result<Vec<T>, D::Error> {
+    d.read_map(|d, s| (0..s).map(|i|
+        d.read_map_elt_key(i, |d| d.read_str()).and_then(|_|
+        d.read_map_elt_val(i, Decodable::decode))).collect())
+}
+
+// Numeric code enum, values not known to this library are decoded into `Unknown` variant
+macro_rules! impl_code_enum {
+    ($cls:ident { $($variant:ident = $value:tt),+ }) => {
+        #[derive(Debug, PartialEq, Clone, Copy)]
+        pub enum $cls {
+            $($variant,)+
+            Unknown(u8)
+        }
+
+        impl From<u8> for $cls {
+            fn from(v: u8) -> $cls {
+                match v {
+                    $($value => $cls::$variant,)+
+                    _ => $cls::Unknown(v)
+                }
+            }
+        }
+
+        impl From<$cls> for u8 {
+            fn from(v: $cls) -> u8 {
+                match v {
+                    $($cls::$variant => $value,)+
+                    $cls::Unknown(v) => v
+                }
+            }
+        }
+
+        impl Decodable for $cls {
+            fn decode<D: Decoder>(d: &mut D) -> Result<$cls, D::Error> {
+                d.read_u8().map(From::from)
+            }
+        }
+
+        impl Encodable for $cls {
+            fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
+                e.emit_u8(From::from(*self))
+            }
+        }
     }
 }
 
+impl_code_enum!(PocketItemHas { No = 0, Yes = 1, Is = 2 });
+
 #[derive(Debug, PartialEq)]
 pub struct PocketAddedItem {
     pub item_id: u64, // String
@@ -306,13 +456,13 @@ pub struct PocketAddedItem {
     pub content_length: usize, // String
     pub word_count: usize, // String
     pub encoding: String,
-    pub mime_type: String, // must be Option<Mime>
+    pub mime_type: Option<Mime>,
     pub lang: String,
     pub title: String,
     pub excerpt: String,
 
-    pub date_published: String, // must be Tm or Timespec
-    pub date_resolved: String, // must be Tm or Timespec
+    pub date_published: Option<Timespec>, // "YYYY-MM-DD HH:MM:SS" String
+    pub date_resolved: Option<Timespec>, // "YYYY-MM-DD HH:MM:SS" String
 
     pub resolved_id: u64, // String
     pub resolved_url: Url,
@@ -331,15 +481,15 @@ pub struct PocketAddedItem {
     pub has_image: PocketItemHas, // String
     pub has_video: PocketItemHas, // String
 
-    //pub tags: Vec<ItemTag>, // ???
-    //pub authors: Vec<ItemAuthor>, // ???
+    pub tags: Vec<ItemTag>, // encoded as object with tag keys
+    pub authors: Vec<ItemAuthor>, // encoded as object with author_id keys
     pub videos: Vec<ItemVideo>, // encoded as object with integer indices
     pub images: Vec<ItemImage>, // if present, as empty array otherwise
 }
 
 impl Decodable for PocketAddedItem {
     fn decode<D: Decoder>(d: &mut D) -> Result<PocketAddedItem, D::Error> {
-        d.read_struct("PocketAddedItem", 28, |d| Ok(PocketAddedItem {
+        d.read_struct("PocketAddedItem", 30, |d| Ok(PocketAddedItem {
             item_id: try!(d.read_struct_field("item_id", 0, |d| d.read_u64())),
             extended_item_id: try!(d.read_struct_field("extended_item_id", 1, |d| d.read_u64())),
 
@@ -348,13 +498,17 @@ impl Decodable for PocketAddedItem {
             content_length: try!(d.read_struct_field("content_length", 4, |d| d.read_usize())),
             word_count: try!(d.read_struct_field("word_count", 5, |d| d.read_usize())),
             encoding: try!(d.read_struct_field("encoding", 6, |d| d.read_str())),
-            mime_type: try!(d.read_struct_field("mime_type", 7, |d| d.read_str())),
+            mime_type: try!(d.read_struct_field("mime_type", 7, |d| d.read_option(|d, b| if b {
+                d.read_str().and_then(|v| v.parse().map(Some).map_err(|_| d.error(&*format!("invalid MIME type: {}", v))))
+            } else {
+                Ok(None)
+            }))),
             lang: try!(d.read_struct_field("lang", 8, |d| d.read_str())),
             title: try!(d.read_struct_field("title", 9, |d| d.read_str())),
             excerpt: try!(d.read_struct_field("excerpt", 10, |d| d.read_str())),
 
-            date_published: try!(d.read_struct_field("date_published", 11, |d| d.read_str())),
-            date_resolved: try!(d.read_struct_field("date_resolved", 12, |d| d.read_str())),
+            date_published: try!(d.read_struct_field("date_published", 11, read_date)),
+            date_resolved: try!(d.read_struct_field("date_resolved", 12, read_date)),
 
             resolved_id: try!(d.read_struct_field("resolved_id", 13, |d| d.read_u64())),
             resolved_url: try!(d.read_struct_field("resolved_url", 14, Decodable::decode)),
@@ -373,11 +527,13 @@ impl Decodable for PocketAddedItem {
             has_image: try!(d.read_struct_field("has_image", 24, Decodable::decode)),
             has_video: try!(d.read_struct_field("has_video", 25, Decodable::decode)),
 
-            videos: try!(d.read_struct_field("videos", 26, |d| d.read_seq(|d, s|
-                Ok((0..s).flat_map(|i| d.read_seq_elt(i, Decodable::decode)).into_iter().collect())
+            tags: try!(d.read_struct_field("tags", 26, read_keyed_list)),
+            authors: try!(d.read_struct_field("authors", 27, read_keyed_list)),
+            videos: try!(d.read_struct_field("videos", 28, |d| d.read_seq(|d, s|
+                (0..s).map(|i| d.read_seq_elt(i, Decodable::decode)).collect()
             ))),
-            images: try!(d.read_struct_field("images", 27, |d| d.read_seq(|d, s|
-                Ok((0..s).flat_map(|i| d.read_seq_elt(i, Decodable::decode)).into_iter().collect())
+            images: try!(d.read_struct_field("images", 29, |d| d.read_seq(|d, s|
+                (0..s).map(|i| d.read_seq_elt(i, Decodable::decode)).collect()
             )))
         }))
     }
@@ -389,8 +545,9 @@ pub struct PocketAddResponse {
     status: u16
 }
 
-pub struct PocketGetRequest<'a> {
-    pocket: &'a mut Pocket,
+// Request is sent with `Pocket` by default, `PocketAsync` has its own `filter()`
+pub struct PocketGetRequest<'a, C = &'a mut Pocket> {
+    pocket: C,
 
     search: Option<&'a str>,
     domain: Option<&'a str>,
@@ -409,28 +566,12 @@ pub struct PocketGetRequest<'a> {
 
 impl<'a> Encodable for PocketGetRequest<'a> {
     fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
-        e.emit_struct("PocketGetRequest", 13, |e| {
-            e.emit_struct_field("consumer_key", 0, |e| self.pocket.consumer_key.encode(e)).and_then(|_|
-            e.emit_struct_field("access_token", 1, |e| self.pocket.access_token.as_ref().unwrap().encode(e))).and_then(|_|
-            e.emit_struct_field("search", 2, |e| self.search.encode(e))).and_then(|_|
-            e.emit_struct_field("domain", 3, |e| self.domain.encode(e))).and_then(|_|
-
-            e.emit_struct_field("tag", 4, |e| self.tag.encode(e))).and_then(|_|
-            e.emit_struct_field("state", 5, |e| self.state.encode(e))).and_then(|_|
-            e.emit_struct_field("content_type", 6, |e| self.content_type.encode(e))).and_then(|_|
-            e.emit_struct_field("detail_type", 7, |e| self.detail_type.encode(e))).and_then(|_|
-            e.emit_struct_field("favorite", 8, |e| self.favorite.encode(e))).and_then(|_|
-            e.emit_struct_field("since", 9, |e| self.since.map(|v| v.sec).encode(e))).and_then(|_|
-
-            e.emit_struct_field("sort", 10, |e| self.sort.encode(e))).and_then(|_|
-            e.emit_struct_field("count", 11, |e| self.count.encode(e))).and_then(|_|
-            e.emit_struct_field("offset", 12, |e| self.offset.encode(e)))
-        })
+        self.encode_with(&*self.pocket.consumer_key, self.pocket.access_token(), e)
     }
 }
 
-impl<'a> PocketGetRequest<'a> {
-    fn new(pocket: &'a mut Pocket) -> PocketGetRequest<'a> {
+impl<'a, C> PocketGetRequest<'a, C> {
+    fn new(pocket: C) -> PocketGetRequest<'a, C> {
         PocketGetRequest {
             pocket: pocket,
             search: None,
@@ -447,118 +588,251 @@ impl<'a> PocketGetRequest<'a> {
         }
     }
 
-    pub fn search<'b>(&'b mut self, search: &'a str) -> &'b mut PocketGetRequest<'a> {
+    fn encode_with<S: Encoder>(&self, consumer_key: &str, access_token: Option<&str>, e: &mut S) -> Result<(), S::Error> {
+        e.emit_struct("PocketGetRequest", 13, |e| {
+            e.emit_struct_field("consumer_key", 0, |e| consumer_key.encode(e)).and_then(|_|
+            e.emit_struct_field("access_token", 1, |e| access_token.encode(e))).and_then(|_|
+            e.emit_struct_field("search", 2, |e| self.search.encode(e))).and_then(|_|
+            e.emit_struct_field("domain", 3, |e| self.domain.encode(e))).and_then(|_|
+
+            e.emit_struct_field("tag", 4, |e| self.tag.encode(e))).and_then(|_|
+            e.emit_struct_field("state", 5, |e| self.state.encode(e))).and_then(|_|
+            e.emit_struct_field("content_type", 6, |e| self.content_type.encode(e))).and_then(|_|
+            e.emit_struct_field("detail_type", 7, |e| self.detail_type.encode(e))).and_then(|_|
+            e.emit_struct_field("favorite", 8, |e| self.favorite.map(|v| v as u8).encode(e))).and_then(|_|
+            e.emit_struct_field("since", 9, |e| self.since.map(|v| v.sec).encode(e))).and_then(|_|
+
+            e.emit_struct_field("sort", 10, |e| self.sort.encode(e))).and_then(|_|
+            e.emit_struct_field("count", 11, |e| self.count.encode(e))).and_then(|_|
+            e.emit_struct_field("offset", 12, |e| self.offset.encode(e)))
+        })
+    }
+
+    pub fn search<'b>(&'b mut self, search: &'a str) -> &'b mut PocketGetRequest<'a, C> {
         self.search = Some(search);
         self
     }
 
-    pub fn domain<'b>(&'b mut self, domain: &'a str) -> &'b mut PocketGetRequest<'a> {
+    pub fn domain<'b>(&'b mut self, domain: &'a str) -> &'b mut PocketGetRequest<'a, C> {
         self.domain = Some(domain);
         self
     }
 
-    pub fn tag<'b>(&'b mut self, tag: PocketGetTag<'a>) -> &'b mut PocketGetRequest<'a> {
+    pub fn tag<'b>(&'b mut self, tag: PocketGetTag<'a>) -> &'b mut PocketGetRequest<'a, C> {
         self.tag = Some(tag);
         self
     }
 
-    pub fn state<'b>(&'b mut self, state: PocketGetState) -> &'b mut PocketGetRequest<'a> {
+    pub fn state<'b>(&'b mut self, state: PocketGetState) -> &'b mut PocketGetRequest<'a, C> {
         self.state = Some(state);
         self
     }
 
-    pub fn content_type<'b>(&'b mut self, content_type: PocketGetType) -> &'b mut PocketGetRequest<'a> {
+    pub fn content_type<'b>(&'b mut self, content_type: PocketGetType) -> &'b mut PocketGetRequest<'a, C> {
         self.content_type = Some(content_type);
         self
     }
 
-    pub fn detail_type<'b>(&'b mut self, detail_type: PocketGetDetail) -> &'b mut PocketGetRequest<'a> {
+    pub fn detail_type<'b>(&'b mut self, detail_type: PocketGetDetail) -> &'b mut PocketGetRequest<'a, C> {
         self.detail_type = Some(detail_type);
         self
     }
 
-    pub fn complete<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
+    pub fn complete<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a, C> {
         self.detail_type(PocketGetDetail::Complete)
     }
 
-    pub fn simple<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
+    pub fn simple<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a, C> {
         self.detail_type(PocketGetDetail::Simple)
     }
 
-    pub fn archived<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
+    pub fn archived<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a, C> {
         self.state(PocketGetState::Archive)
     }
 
-    pub fn unread<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
+    pub fn unread<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a, C> {
         self.state(PocketGetState::Unread)
     }
 
-    pub fn articles<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
+    pub fn articles<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a, C> {
         self.content_type(PocketGetType::Article)
     }
 
-    pub fn videos<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
+    pub fn videos<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a, C> {
         self.content_type(PocketGetType::Video)
     }
 
-    pub fn images<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
+    pub fn images<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a, C> {
         self.content_type(PocketGetType::Image)
     }
 
-    pub fn favorite<'b>(&'b mut self, fav: bool) -> &'b mut PocketGetRequest<'a> {
+    pub fn favorite<'b>(&'b mut self, fav: bool) -> &'b mut PocketGetRequest<'a, C> {
         self.favorite = Some(fav);
         self
     }
 
-    pub fn since<'b>(&'b mut self, since: Timespec) -> &'b mut PocketGetRequest<'a> {
+    pub fn since<'b>(&'b mut self, since: Timespec) -> &'b mut PocketGetRequest<'a, C> {
         self.since = Some(since);
         self
     }
 
-    pub fn sort<'b>(&'b mut self, sort: PocketGetSort) -> &'b mut PocketGetRequest<'a> {
+    pub fn sort<'b>(&'b mut self, sort: PocketGetSort) -> &'b mut PocketGetRequest<'a, C> {
         self.sort = Some(sort);
         self
     }
 
-    pub fn sort_by_newest<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
+    pub fn sort_by_newest<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a, C> {
         self.sort(PocketGetSort::Newest)
     }
 
-    pub fn sort_by_oldest<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
+    pub fn sort_by_oldest<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a, C> {
         self.sort(PocketGetSort::Oldest)
     }
 
-    pub fn sort_by_title<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
+    pub fn sort_by_title<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a, C> {
         self.sort(PocketGetSort::Title)
     }
 
-    pub fn sort_by_site<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a> {
+    pub fn sort_by_site<'b>(&'b mut self) -> &'b mut PocketGetRequest<'a, C> {
         self.sort(PocketGetSort::Site)
     }
 
-    pub fn offset<'b>(&'b mut self, offset: usize) -> &'b mut PocketGetRequest<'a> {
+    pub fn offset<'b>(&'b mut self, offset: usize) -> &'b mut PocketGetRequest<'a, C> {
         self.offset = Some(offset);
         self
     }
 
-    pub fn count<'b>(&'b mut self, count: usize) -> &'b mut PocketGetRequest<'a> {
+    pub fn count<'b>(&'b mut self, count: usize) -> &'b mut PocketGetRequest<'a, C> {
         self.count = Some(count);
         self
     }
 
-    pub fn slice<'b>(&'b mut self, offset: usize, count: usize) -> &'b mut PocketGetRequest<'a> {
+    pub fn slice<'b>(&'b mut self, offset: usize, count: usize) -> &'b mut PocketGetRequest<'a, C> {
         self.offset(offset).count(count)
     }
 
+}
+
+impl<'a> PocketGetRequest<'a> {
+    // Items are ordered as requested with `sort()`,
+    // fails with `PocketError::Decode` if any item failed to decode
     pub fn get(self) -> PocketResult<Vec<PocketItem>> {
-        let mut request = String::new();
-        {
-            let mut encoder = json::Encoder::new(&mut request);
-            self.encode(&mut encoder).unwrap();
+        self.get_response().and_then(PocketGetResponse::into_list)
+    }
+
+    // Items keyed by item_id
+    pub fn get_map(self) -> PocketResult<BTreeMap<u64, PocketItem>> {
+        self.get().map(|v| v.into_iter().map(|item| (item.item_id, item)).collect())
+    }
+
+    // Returns items decoded successfully along with decoding errors for the rest
+    pub fn get_lenient(self) -> PocketResult<(Vec<PocketItem>, Vec<PocketDecodeError>)> {
+        self.get_response().map(|v| (v.list, v.errors))
+    }
+
+    // Full response with items decoded successfully in `list` and decoding errors for the rest in `errors`
+    pub fn get_response(mut self) -> PocketResult<PocketGetResponse> {
+        self.fetch()
+    }
+
+    // Iterates over pages of `page_size` items starting from `offset()`,
+    // requesting next page from server only when previous one is consumed
+    pub fn pages(self, page_size: usize) -> PocketGetPages<'a> {
+        PocketGetPages {
+            offset: self.offset.unwrap_or(0),
+            request: self,
+            page_size: page_size,
+            done: page_size == 0
         }
+    }
+
+    fn fetch(&mut self) -> PocketResult<PocketGetResponse> {
+        try!(self.pocket.require_access_token());
+        let request = try!(json::encode(self));
+        self.pocket.post("get", &*request)
+            .and_then(|s| PocketGetResponse::from_str(&*s))
+    }
+}
+
+// Stops after empty or short page, or after request failed. Each page is a full response,
+// so items failed to decode are in its `errors` along with the rest of items in `list`.
+pub struct PocketGetPages<'a> {
+    request: PocketGetRequest<'a>,
+    page_size: usize,
+    offset: usize,
+    done: bool
+}
 
-        self.pocket.request("https://getpocket.com/v3/get", &*request)
-            .map(|v: PocketGetResponse| v.list)
+impl<'a> PocketGetPages<'a> {
+    pub fn items(self) -> PocketGetItems<'a> {
+        PocketGetItems {
+            pages: self,
+            page: Vec::new().into_iter(),
+            errors: Vec::new().into_iter()
+        }
+    }
+
+    // Full response for the next page, the last one may be empty
+    fn next_response(&mut self) -> Option<PocketResult<PocketGetResponse>> {
+        if self.done {
+            return None;
+        }
+
+        self.request.offset = Some(self.offset);
+        self.request.count = Some(self.page_size);
+        let response = match self.request.fetch() {
+            Ok(response) => response,
+            Err(e) => {
+                self.done = true;
+                return Some(Err(e));
+            }
+        };
+
+        let received = response.list.len() + response.deleted.len() + response.errors.len();
+        self.offset += received;
+        self.done = received < self.page_size;
+        Some(Ok(response))
+    }
+}
+
+impl<'a> Iterator for PocketGetPages<'a> {
+    type Item = PocketResult<PocketGetResponse>;
+
+    fn next(&mut self) -> Option<PocketResult<PocketGetResponse>> {
+        match self.next_response() {
+            Some(Ok(ref response)) if response.list.is_empty() && response.deleted.is_empty() && response.errors.is_empty() => None,
+            other => other
+        }
+    }
+}
+
+// Items failed to decode are reported as `PocketError::Decode` after the rest of items of their page
+pub struct PocketGetItems<'a> {
+    pages: PocketGetPages<'a>,
+    page: ::std::vec::IntoIter<PocketItem>,
+    errors: ::std::vec::IntoIter<PocketDecodeError>
+}
+
+impl<'a> Iterator for PocketGetItems<'a> {
+    type Item = PocketResult<PocketItem>;
+
+    fn next(&mut self) -> Option<PocketResult<PocketItem>> {
+        loop {
+            if let Some(item) = self.page.next() {
+                return Some(Ok(item));
+            }
+            if let Some(error) = self.errors.next() {
+                return Some(Err(PocketError::Decode(error)));
+            }
+            match self.pages.next() {
+                Some(Ok(page)) => {
+                    self.page = page.list.into_iter();
+                    self.errors = page.errors.into_iter();
+                },
+                Some(Err(e)) => return Some(Err(e)),
+                None => return None
+            }
+        }
     }
 }
 
@@ -645,53 +919,116 @@ impl Encodable for PocketGetType {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct PocketGetResponse {
-    list: Vec<PocketItem>, // must be Vec
-    status: u16,
-    complete: bool, // must be bool
-    error: Option<String>,
-    //search_meta: PocketSearchMeta,
-    since: Timespec,
-}
-
-impl Decodable for PocketGetResponse {
-    fn decode<D: Decoder>(d: &mut D) -> Result<PocketGetResponse, D::Error> {
-        d.read_struct("PocketGetResponse", 5, |d| Ok(PocketGetResponse {
-            list: try!(d.read_struct_field("list", 0, |d| d.read_map(|d, s|
-                Ok((0..s).flat_map(|i|
-                    d.read_map_elt_key(i, |d| d.read_str()).and_then(|_|
-                    d.read_map_elt_val(i, Decodable::decode)).into_iter())
-                .collect())
-            ))),
-            status: try!(d.read_struct_field("status", 1, |d| d.read_u16())),
-            complete: try!(d.read_struct_field("complete", 2, |d| d.read_u8().map(|v| v != 0))),
-            error: try!(d.read_struct_field("error", 3, |d| d.read_option(|d, b| if b { d.read_str().map(Some) } else { Ok(None) }))),
-            since: try!(d.read_struct_field("since", 4, |d| d.read_u64().map(|v| Timespec::new(v as i64, 0))))
-        }))
+    pub list: Vec<PocketItem>, // ordered by sort_id
+    pub status: u16,
+    pub complete: bool,
+    pub error: Option<String>,
+    pub search_meta: Option<PocketSearchMeta>,
+    pub since: Timespec, // pass to `PocketGetRequest::since()` to get changes after this request
+    pub deleted: Vec<u64>, // items deleted since `since()`, sent as stubs with item_id and status only
+    pub errors: Vec<PocketDecodeError>, // items failed to decode
+}
+
+#[derive(RustcDecodable, Debug, PartialEq, Clone)]
+pub struct PocketSearchMeta {
+    pub search_type: String,
+    pub total_result_count: Option<usize>, // all items matching request, regardless of offset and count
+    pub count: Option<usize>,
+    pub offset: Option<usize>,
+    pub has_more: Option<bool>
+}
+
+// Item from `/v3/get` list or `/v3/send` results which failed to decode
+#[derive(Debug, PartialEq)]
+pub struct PocketDecodeError {
+    pub item_id: String, // key in list
+    pub field: Option<String>, // path to failed field, e.g. "images.1.src"
+    pub error: json::DecoderError
+}
+
+fn decode_item<T: Decodable>(item_id: String, value: Json) -> Result<T, PocketDecodeError> {
+    let mut decoder = PocketDecoder::new(value);
+    match Decodable::decode(&mut decoder) {
+        Ok(item) => Ok(item),
+        Err(e) => Err(PocketDecodeError {
+            item_id: item_id,
+            field: decoder.error_path().map(|v| v.to_string()),
+            error: e
+        })
     }
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub enum PocketItemStatus {
-    Normal = 0,
-    Archived = 1,
-    Deleted = 2
-}
+impl PocketGetResponse {
+    fn from_str(s: &str) -> PocketResult<PocketGetResponse> {
+        Json::from_str(s).map_err(From::from)
+            .and_then(|v| PocketGetResponse::from_json(v).map_err(From::from))
+    }
+
+    // Items only, fails with `PocketError::Decode` if any item failed to decode
+    fn into_list(mut self) -> PocketResult<Vec<PocketItem>> {
+        match self.errors.len() {
+            0 => Ok(self.list),
+            _ => Err(PocketError::Decode(self.errors.remove(0)))
+        }
+    }
+
+    // Every item is decoded separately, so one bad item doesn't break the others
+    fn from_json(json: Json) -> Result<PocketGetResponse, json::DecoderError> {
+        let mut obj: BTreeMap<String, Json> = match json {
+            Json::Object(obj) => obj,
+            other => return Err(json::DecoderError::ExpectedError("Object".to_string(), other.to_string()))
+        };
+
+        let entries: Vec<(String, Json)> = match obj.remove("list") {
+            Some(Json::Object(list)) => list.into_iter().collect(),
+            Some(Json::Array(ref list)) if list.is_empty() => Vec::new(),
+            Some(Json::Null) | None => Vec::new(),
+            Some(other) => return Err(json::DecoderError::ExpectedError("Object".to_string(), other.to_string()))
+        };
+
+        let mut list = Vec::new();
+        let mut deleted = Vec::new();
+        let mut errors = Vec::new();
+        for (key, value) in entries {
+            let is_deleted = match value.find("status") {
+                Some(&Json::String(ref s)) => s == "2",
+                Some(&Json::U64(2)) | Some(&Json::I64(2)) => true,
+                _ => false
+            };
+            if is_deleted {
+                if let Ok(item_id) = key.parse() {
+                    deleted.push(item_id);
+                    continue;
+                }
+            }
 
-impl Decodable for PocketItemStatus {
-    fn decode<D: Decoder>(d: &mut D) -> Result<PocketItemStatus, D::Error> {
-        d.read_u8().map(|v| match v {
-            0 => PocketItemStatus::Normal,
-            1 => PocketItemStatus::Archived,
-            2 => PocketItemStatus::Deleted,
-            _ => unreachable!()
+            match decode_item(key, value) {
+                Ok(item) => list.push(item),
+                Err(e) => errors.push(e)
+            }
+        }
+        // list is an object keyed by item_id, so the order requested is only kept in sort_id
+        list.sort_by(|a: &PocketItem, b: &PocketItem| a.sort_id.cmp(&b.sort_id));
+
+        Ok(PocketGetResponse {
+            list: list,
+            status: try!(decode_field(&mut obj, "status")),
+            complete: try!(decode_field(&mut obj, "complete")),
+            error: try!(decode_field(&mut obj, "error")),
+            search_meta: try!(decode_field(&mut obj, "search_meta")),
+            since: try!(decode_field(&mut obj, "since").map(|v: u64| Timespec::new(v as i64, 0))),
+            deleted: deleted,
+            errors: errors
         })
     }
 }
 
+impl_code_enum!(PocketItemStatus { Normal = 0, Archived = 1, Deleted = 2 });
+
 // See also PocketAddedItem
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct PocketItem {
     pub item_id: u64,
 
@@ -701,10 +1038,10 @@ pub struct PocketItem {
     pub word_count: usize,
     pub excerpt: String,
 
-    pub time_added: Timespec,
-    pub time_read: Timespec,
-    pub time_updated: Timespec,
-    pub time_favorited: Timespec,
+    pub time_added: Option<Timespec>, // None if "0"
+    pub time_read: Option<Timespec>,
+    pub time_updated: Option<Timespec>,
+    pub time_favorited: Option<Timespec>,
 
     pub favorite: bool,
 
@@ -722,11 +1059,87 @@ pub struct PocketItem {
     pub status: PocketItemStatus,
     pub images: Option<Vec<ItemImage>>,
     pub videos: Option<Vec<ItemVideo>>,
+    pub tags: Vec<ItemTag>, // with complete details only
+    pub authors: Vec<ItemAuthor>, // with complete details only
+
+    pub lang: Option<String>,
+    pub time_to_read: Option<u32>, // minutes
+    pub listen_duration_estimate: Option<u32>, // seconds
+    pub top_image_url: Option<Url>,
+    pub amp_url: Option<Url>,
+    pub domain_metadata: Option<ItemDomainMetadata>,
+}
+
+impl PocketItem {
+    // Item was read at least once (time_read is set)
+    pub fn is_read(&self) -> bool {
+        self.time_read.is_some()
+    }
+
+    pub fn is_favorited(&self) -> bool {
+        self.favorite
+    }
+
+    pub fn is_archived(&self) -> bool {
+        self.status == PocketItemStatus::Archived
+    }
+
+    // Deleted items are only returned by requests with `since`
+    pub fn is_deleted(&self) -> bool {
+        self.status == PocketItemStatus::Deleted
+    }
+}
+
+// Encoded in the same form it's decoded from, so items can be stored locally,
+// lists are encoded as arrays and timestamps as numbers
+impl Encodable for PocketItem {
+    fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
+        let timestamp = |t: Option<Timespec>| t.map_or(0, |v| v.sec);
+        e.emit_struct("PocketItem", 29, |e| {
+            e.emit_struct_field("item_id", 0, |e| self.item_id.encode(e)).and_then(|_|
+
+            e.emit_struct_field("given_url", 1, |e| self.given_url.encode(e))).and_then(|_|
+            e.emit_struct_field("given_title", 2, |e| self.given_title.encode(e))).and_then(|_|
+
+            e.emit_struct_field("word_count", 3, |e| self.word_count.encode(e))).and_then(|_|
+            e.emit_struct_field("excerpt", 4, |e| self.excerpt.encode(e))).and_then(|_|
+
+            e.emit_struct_field("time_added", 5, |e| timestamp(self.time_added).encode(e))).and_then(|_|
+            e.emit_struct_field("time_read", 6, |e| timestamp(self.time_read).encode(e))).and_then(|_|
+            e.emit_struct_field("time_updated", 7, |e| timestamp(self.time_updated).encode(e))).and_then(|_|
+            e.emit_struct_field("time_favorited", 8, |e| timestamp(self.time_favorited).encode(e))).and_then(|_|
+
+            e.emit_struct_field("favorite", 9, |e| (self.favorite as u8).encode(e))).and_then(|_|
+            e.emit_struct_field("is_index", 10, |e| (self.is_index as u8).encode(e))).and_then(|_|
+            e.emit_struct_field("is_article", 11, |e| (self.is_article as u8).encode(e))).and_then(|_|
+            e.emit_struct_field("has_image", 12, |e| self.has_image.encode(e))).and_then(|_|
+            e.emit_struct_field("has_video", 13, |e| self.has_video.encode(e))).and_then(|_|
+
+            e.emit_struct_field("resolved_id", 14, |e| self.resolved_id.encode(e))).and_then(|_|
+            e.emit_struct_field("resolved_title", 15, |e| self.resolved_title.encode(e))).and_then(|_|
+            e.emit_struct_field("resolved_url", 16, |e| self.resolved_url.encode(e))).and_then(|_|
+
+            e.emit_struct_field("sort_id", 17, |e| self.sort_id.encode(e))).and_then(|_|
+            e.emit_struct_field("status", 18, |e| self.status.encode(e))).and_then(|_|
+
+            e.emit_struct_field("videos", 19, |e| self.videos.encode(e))).and_then(|_|
+            e.emit_struct_field("images", 20, |e| self.images.encode(e))).and_then(|_|
+            e.emit_struct_field("tags", 21, |e| self.tags.encode(e))).and_then(|_|
+            e.emit_struct_field("authors", 22, |e| self.authors.encode(e))).and_then(|_|
+
+            e.emit_struct_field("lang", 23, |e| self.lang.encode(e))).and_then(|_|
+            e.emit_struct_field("time_to_read", 24, |e| self.time_to_read.encode(e))).and_then(|_|
+            e.emit_struct_field("listen_duration_estimate", 25, |e| self.listen_duration_estimate.encode(e))).and_then(|_|
+            e.emit_struct_field("top_image_url", 26, |e| self.top_image_url.encode(e))).and_then(|_|
+            e.emit_struct_field("amp_url", 27, |e| self.amp_url.encode(e))).and_then(|_|
+            e.emit_struct_field("domain_metadata", 28, |e| self.domain_metadata.encode(e)))
+        })
+    }
 }
 
 impl Decodable for PocketItem {
     fn decode<D: Decoder>(d: &mut D) -> Result<PocketItem, D::Error> {
-        d.read_struct("PocketItem", 21, |d| Ok(PocketItem {
+        d.read_struct("PocketItem", 29, |d| Ok(PocketItem {
             item_id: try!(d.read_struct_field("item_id", 0, |d| d.read_u64())),
 
             given_url: try!(d.read_struct_field("given_url", 1, Decodable::decode)),
@@ -735,10 +1148,10 @@ impl Decodable for PocketItem {
             word_count: try!(d.read_struct_field("word_count", 3, |d| d.read_usize())),
             excerpt: try!(d.read_struct_field("excerpt", 4, |d| d.read_str())),
 
-            time_added: try!(d.read_struct_field("time_added", 5, |d| d.read_u64().map(|v| Timespec::new(v as i64, 0)))),
-            time_read: try!(d.read_struct_field("time_read", 6, |d| d.read_u64().map(|v| Timespec::new(v as i64, 0)))),
-            time_updated: try!(d.read_struct_field("time_updated", 7, |d| d.read_u64().map(|v| Timespec::new(v as i64, 0)))),
-            time_favorited: try!(d.read_struct_field("time_favorited", 8, |d| d.read_u64().map(|v| Timespec::new(v as i64, 0)))),
+            time_added: try!(d.read_struct_field("time_added", 5, read_timestamp)),
+            time_read: try!(d.read_struct_field("time_read", 6, read_timestamp)),
+            time_updated: try!(d.read_struct_field("time_updated", 7, read_timestamp)),
+            time_favorited: try!(d.read_struct_field("time_favorited", 8, read_timestamp)),
 
             favorite: try!(d.read_struct_field("favorite", 9, |d| d.read_u8().map(|v| v != 0))),
             is_index: try!(d.read_struct_field("is_index", 10, |d| d.read_u8().map(|v| v != 0))),
@@ -754,15 +1167,24 @@ impl Decodable for PocketItem {
             status: try!(d.read_struct_field("status", 18, Decodable::decode)),
 
             videos: try!(d.read_struct_field("videos", 19, |d| d.read_option(|d, b| if b {
-                d.read_map(|d, s| Ok((0..s).flat_map(|i| d.read_map_elt_val(i, Decodable::decode).into_iter()).collect())).map(Some)
+                read_keyed_list(d).map(Some)
             } else {
                 Ok(None)
             }))),
             images: try!(d.read_struct_field("images", 20, |d| d.read_option(|d, b| if b {
-                d.read_map(|d, s| Ok((0..s).flat_map(|i| d.read_map_elt_val(i, Decodable::decode).into_iter()).collect())).map(Some)
+                read_keyed_list(d).map(Some)
             } else {
                 Ok(None)
-            })))
+            }))),
+            tags: try!(d.read_struct_field("tags", 21, read_keyed_list)),
+            authors: try!(d.read_struct_field("authors", 22, read_keyed_list)),
+
+            lang: try!(d.read_struct_field("lang", 23, Decodable::decode)),
+            time_to_read: try!(d.read_struct_field("time_to_read", 24, Decodable::decode)),
+            listen_duration_estimate: try!(d.read_struct_field("listen_duration_estimate", 25, Decodable::decode)),
+            top_image_url: try!(d.read_struct_field("top_image_url", 26, Decodable::decode)),
+            amp_url: try!(d.read_struct_field("amp_url", 27, Decodable::decode)),
+            domain_metadata: try!(d.read_struct_field("domain_metadata", 28, Decodable::decode))
         }))
     }
 }
@@ -776,8 +1198,53 @@ pub struct PocketAddAction<'a> {
     url: Option<&'a Url>
 }
 
+impl<'a> PocketAddAction<'a> {
+    pub fn from_url(url: &'a Url) -> PocketAddAction<'a> {
+        PocketAddAction {
+            item_id: None,
+            ref_id: None,
+            tags: None,
+            time: None,
+            title: None,
+            url: Some(url)
+        }
+    }
+
+    pub fn from_item_id(item_id: u64) -> PocketAddAction<'a> {
+        PocketAddAction {
+            item_id: Some(item_id),
+            ref_id: None,
+            tags: None,
+            time: None,
+            title: None,
+            url: None
+        }
+    }
+
+    pub fn ref_id(mut self, ref_id: &'a str) -> PocketAddAction<'a> {
+        self.ref_id = Some(ref_id);
+        self
+    }
+
+    pub fn tags(mut self, tags: &'a str) -> PocketAddAction<'a> {
+        self.tags = Some(tags);
+        self
+    }
+
+    pub fn title(mut self, title: &'a str) -> PocketAddAction<'a> {
+        self.title = Some(title);
+        self
+    }
+
+    pub fn at(mut self, time: Timespec) -> PocketAddAction<'a> {
+        self.time = Some(time.sec as u64);
+        self
+    }
+}
+
 impl<'a> PocketAction for PocketAddAction<'a> {
     fn name(&self) -> &'static str { "add" }
+    fn item_id(&self) -> Option<u64> { self.item_id }
 }
 
 impl<'a> JsonEncodable for PocketAddAction<'a> {
@@ -806,16 +1273,33 @@ pub struct PocketTagsAddAction<'a> {
     time: Option<u64>,
 }
 
+impl<'a> PocketTagsAddAction<'a> {
+    pub fn new(item_id: u64, tags: &'a str) -> PocketTagsAddAction<'a> {
+        PocketTagsAddAction {
+            item_id: item_id,
+            tags: tags,
+            time: None
+        }
+    }
+
+    pub fn at(mut self, time: Timespec) -> PocketTagsAddAction<'a> {
+        self.time = Some(time.sec as u64);
+        self
+    }
+}
+
 impl<'a> PocketAction for PocketTagsAddAction<'a> {
     fn name(&self) -> &'static str { "tags_add" }
+    fn item_id(&self) -> Option<u64> { Some(self.item_id) }
 }
 
 impl<'a> JsonEncodable for PocketTagsAddAction<'a> {
     fn json_encode(&self, e: &mut json::Encoder) -> Result<(), json::EncoderError> {
-        e.emit_struct("PocketTagsAddAction", 3, |e| {
+        e.emit_struct("PocketTagsAddAction", 4, |e| {
             e.emit_struct_field("name", 0, |e| e.emit_str(self.name())).and_then(|_|
-            e.emit_struct_field("tags", 1, |e| self.tags.encode(e))).and_then(|_|
-            e.emit_struct_field("time", 2, |e| self.time.encode(e)))
+            e.emit_struct_field("item_id", 1, |e| self.item_id.encode(e))).and_then(|_|
+            e.emit_struct_field("tags", 2, |e| self.tags.encode(e))).and_then(|_|
+            e.emit_struct_field("time", 3, |e| self.time.encode(e)))
         })
     }
 }
@@ -826,8 +1310,24 @@ pub struct PocketTagsReplaceAction<'a> {
     time: Option<u64>,
 }
 
+impl<'a> PocketTagsReplaceAction<'a> {
+    pub fn new(item_id: u64, tags: &'a str) -> PocketTagsReplaceAction<'a> {
+        PocketTagsReplaceAction {
+            item_id: item_id,
+            tags: tags,
+            time: None
+        }
+    }
+
+    pub fn at(mut self, time: Timespec) -> PocketTagsReplaceAction<'a> {
+        self.time = Some(time.sec as u64);
+        self
+    }
+}
+
 impl<'a> PocketAction for PocketTagsReplaceAction<'a> {
     fn name(&self) -> &'static str { "tags_replace" }
+    fn item_id(&self) -> Option<u64> { Some(self.item_id) }
 }
 
 impl<'a> JsonEncodable for PocketTagsReplaceAction<'a> {
@@ -843,39 +1343,66 @@ impl<'a> JsonEncodable for PocketTagsReplaceAction<'a> {
 
 impl_item_pocket_action!("tags_clear", PocketTagsClearAction);
 
+// Renames the tag on all items, so no item_id here
 pub struct PocketTagRenameAction<'a> {
-    item_id: u64,
     old_tag: &'a str,
     new_tag: &'a str,
     time: Option<u64>,
 }
 
+impl<'a> PocketTagRenameAction<'a> {
+    pub fn new(old_tag: &'a str, new_tag: &'a str) -> PocketTagRenameAction<'a> {
+        PocketTagRenameAction {
+            old_tag: old_tag,
+            new_tag: new_tag,
+            time: None
+        }
+    }
+
+    pub fn at(mut self, time: Timespec) -> PocketTagRenameAction<'a> {
+        self.time = Some(time.sec as u64);
+        self
+    }
+}
+
 impl<'a> PocketAction for PocketTagRenameAction<'a> {
     fn name(&self) -> &'static str { "tag_rename" }
+    fn item_id(&self) -> Option<u64> { None }
 }
 
 impl<'a> JsonEncodable for PocketTagRenameAction<'a> {
     fn json_encode(&self, e: &mut json::Encoder) -> Result<(), json::EncoderError> {
-        e.emit_struct("PocketTagRenameAction", 5, |e| {
+        e.emit_struct("PocketTagRenameAction", 4, |e| {
             e.emit_struct_field("name", 0, |e| e.emit_str(self.name())).and_then(|_|
-            e.emit_struct_field("item_id", 1, |e| self.item_id.encode(e))).and_then(|_|
-            e.emit_struct_field("old_tag", 2, |e| self.old_tag.encode(e))).and_then(|_|
-            e.emit_struct_field("new_tag", 3, |e| self.new_tag.encode(e))).and_then(|_|
-            e.emit_struct_field("time", 4, |e| self.time.encode(e)))
+            e.emit_struct_field("old_tag", 1, |e| self.old_tag.encode(e))).and_then(|_|
+            e.emit_struct_field("new_tag", 2, |e| self.new_tag.encode(e))).and_then(|_|
+            e.emit_struct_field("time", 3, |e| self.time.encode(e)))
         })
     }
 }
 
-pub struct PocketSendRequest<'a, 'b> {
-    pocket: &'b mut Pocket,
+pub struct PocketSendRequest<'a> {
+    consumer_key: &'a str,
+    access_token: &'a str,
     actions: &'a [&'a PocketAction]
 }
 
-impl<'a, 'b> JsonEncodable for PocketSendRequest<'a, 'b> {
+impl<'a> PocketSendRequest<'a> {
+    fn to_json(&self) -> PocketResult<String> {
+        let mut request = String::new();
+        {
+            let mut encoder = json::Encoder::new(&mut request);
+            try!(self.json_encode(&mut encoder));
+        }
+        Ok(request)
+    }
+}
+
+impl<'a> JsonEncodable for PocketSendRequest<'a> {
     fn json_encode(&self, e: &mut json::Encoder) -> Result<(), json::EncoderError> {
         e.emit_struct("PocketSendRequest", 3, |e| {
-            e.emit_struct_field("consumer_key", 0, |e| self.pocket.consumer_key.encode(e)).and_then(|_|
-            e.emit_struct_field("access_token", 1, |e| self.pocket.access_token.as_ref().unwrap().encode(e))).and_then(|_|
+            e.emit_struct_field("consumer_key", 0, |e| self.consumer_key.encode(e)).and_then(|_|
+            e.emit_struct_field("access_token", 1, |e| self.access_token.encode(e))).and_then(|_|
             e.emit_struct_field("actions", 2, |e| e.emit_seq(self.actions.len(), |e| {
                 for (i, action) in self.actions.iter().enumerate() {
                     try!(e.emit_seq_elt(i, |e| action.json_encode(e)));
@@ -886,91 +1413,293 @@ impl<'a, 'b> JsonEncodable for PocketSendRequest<'a, 'b> {
     }
 }
 
-#[derive(RustcDecodable)]
-pub struct PocketSendResponse {
+#[derive(Debug, PartialEq)]
+pub struct PocketActionError {
+    pub message: String,
+    pub error_type: String,
+    pub code: u16,
+}
+
+impl Decodable for PocketActionError {
+    fn decode<D: Decoder>(d: &mut D) -> Result<PocketActionError, D::Error> {
+        d.read_struct("PocketActionError", 3, |d| Ok(PocketActionError {
+            message: try!(d.read_struct_field("message", 0, |d| d.read_str())),
+            error_type: try!(d.read_struct_field("type", 1, |d| d.read_str())),
+            code: try!(d.read_struct_field("code", 2, |d| d.read_u16())),
+        }))
+    }
+}
+
+#[derive(Debug)]
+pub struct PocketActionResult {
+    pub name: &'static str,
+    pub item_id: Option<u64>,
+    pub success: bool,
+    pub item: Option<PocketAddedItem>, // for successful "add" actions only
+    pub error: Option<PocketActionError>,
+    pub item_error: Option<PocketDecodeError>, // "add" succeeded, but its item failed to decode
+}
+
+#[derive(Debug)]
+struct PocketSendResponse {
     status: u16,
-    action_results: Vec<bool>
+    action_results: Vec<(bool, Option<PocketAddedItem>, Option<PocketDecodeError>)>, // `true`, `false` or added item object
+    action_errors: Vec<Option<PocketActionError>>, // empty if all actions succeeded
+}
+
+// Decodes and removes object field, like `read_struct_field` does
+fn decode_field<T: Decodable>(obj: &mut BTreeMap<String, Json>, name: &str) -> Result<T, json::DecoderError> {
+    match obj.remove(name) {
+        Some(value) => decode_json(value),
+        None => decode_json(Json::Null).map_err(|_| json::DecoderError::MissingFieldError(name.to_string()))
+    }
+}
+
+impl PocketSendResponse {
+    fn from_str(s: &str) -> PocketResult<PocketSendResponse> {
+        Json::from_str(s).map_err(From::from)
+            .and_then(|v| PocketSendResponse::from_json(v).map_err(From::from))
+    }
+
+    // Results for actions given by their names and item ids, in the order they were sent
+    fn into_results<I: Iterator<Item=(&'static str, Option<u64>)>>(self, actions: I) -> Vec<PocketActionResult> {
+        let mut results = self.action_results.into_iter();
+        let mut errors = self.action_errors.into_iter();
+        actions.map(|(name, item_id)| {
+            let (success, item, item_error) = results.next().unwrap_or((false, None, None));
+            PocketActionResult {
+                name: name,
+                item_id: item_id
+                    .or_else(|| item.as_ref().map(|v| v.item_id))
+                    .or_else(|| item_error.as_ref().and_then(|e| e.item_id.parse().ok())),
+                success: success,
+                item: item,
+                error: errors.next().and_then(|v| v),
+                item_error: item_error
+            }
+        }).collect()
+    }
+
+    fn from_json(json: Json) -> Result<PocketSendResponse, json::DecoderError> {
+        let mut obj: BTreeMap<String, Json> = match json {
+            Json::Object(obj) => obj,
+            other => return Err(json::DecoderError::ExpectedError("Object".to_string(), other.to_string()))
+        };
+
+        let status = try!(decode_field(&mut obj, "status"));
+
+        let action_results = match obj.remove("action_results") {
+            // actions are already applied, so an item failing to decode doesn't fail the others
+            Some(Json::Array(results)) => try!(results.into_iter().map(|v| match v {
+                Json::Boolean(b) => Ok((b, None, None)),
+                item @ Json::Object(_) => {
+                    let item_id = item.find("item_id").map(|v| v.as_string().map(|s| s.to_string()).unwrap_or_else(|| v.to_string()));
+                    Ok(match decode_item(item_id.unwrap_or_else(String::new), item) {
+                        Ok(item) => (true, Some(item), None),
+                        Err(e) => (true, None, Some(e))
+                    })
+                },
+                other => Err(json::DecoderError::ExpectedError("Boolean or Object".to_string(), other.to_string()))
+            }).collect()),
+            Some(other) => return Err(json::DecoderError::ExpectedError("Array".to_string(), other.to_string())),
+            None => return Err(json::DecoderError::MissingFieldError("action_results".to_string()))
+        };
+
+        let action_errors = match obj.remove("action_errors") {
+            Some(Json::Null) | None => Vec::new(),
+            Some(errors) => try!(decode_json(errors))
+        };
+
+        Ok(PocketSendResponse {
+            status: status,
+            action_results: action_results,
+            action_errors: action_errors
+        })
+    }
+}
+
+// URL given to `add()` and `push()`, either parsed already or a string to parse
+pub trait IntoPocketUrl {
+    fn into_url(self) -> Result<Url, UrlError>;
+}
+
+impl IntoPocketUrl for Url {
+    fn into_url(self) -> Result<Url, UrlError> {
+        Ok(self)
+    }
+}
+
+impl<'a> IntoPocketUrl for &'a str {
+    fn into_url(self) -> Result<Url, UrlError> {
+        Url::parse(self)
+    }
+}
+
+impl<'a> IntoPocketUrl for &'a String {
+    fn into_url(self) -> Result<Url, UrlError> {
+        Url::parse(self)
+    }
+}
+
+impl IntoPocketUrl for String {
+    fn into_url(self) -> Result<Url, UrlError> {
+        Url::parse(&*self)
+    }
 }
 
 impl Pocket {
+    #[cfg(feature = "hyper")]
     pub fn new(consumer_key: &str, access_token: Option<&str>) -> Pocket {
+        Pocket::with_urls(consumer_key, access_token,
+                          Url::parse(DEFAULT_BASE_URL).unwrap(),
+                          Url::parse(DEFAULT_AUTHORIZE_URL).unwrap())
+    }
+
+    // API methods are resolved relative to `base_url`, so it should end with a slash,
+    // e.g. "http://127.0.0.1:8080/v3/"
+    #[cfg(feature = "hyper")]
+    pub fn with_urls(consumer_key: &str, access_token: Option<&str>, base_url: Url, authorize_url: Url) -> Pocket {
+        Pocket::with_transport(consumer_key, access_token, base_url, authorize_url, HyperTransport::new())
+    }
+
+    // Without `hyper` feature there's no default transport, so it must be given here,
+    // use `DEFAULT_BASE_URL` and `DEFAULT_AUTHORIZE_URL` to talk to Pocket itself
+    pub fn with_transport<T: PocketTransport + Send + 'static>(consumer_key: &str, access_token: Option<&str>,
+                                                              base_url: Url, authorize_url: Url, transport: T) -> Pocket {
         Pocket {
             consumer_key: consumer_key.to_string(),
             access_token: access_token.map(|v| v.to_string()),
-            code: None,
-            client: Client::new()
+            pending: None,
+            base_url: base_url,
+            authorize_url: authorize_url,
+            transport: Box::new(transport)
         }
     }
 
+    pub fn set_transport<T: PocketTransport + Send + 'static>(&mut self, transport: T) {
+        self.transport = Box::new(transport);
+    }
+
     #[inline] pub fn access_token(&self) -> Option<&str> {
         self.access_token.as_ref().map(|v| &**v)
     }
 
-    fn request<Resp: Decodable>(&mut self, url: &str, data: &str) -> PocketResult<Resp> {
-        let app_json: Mime = "application/json".parse().unwrap();
-        self.client.post(url)
-            .header(XAccept(app_json.clone()))
-            .header(ContentType(app_json.clone()))
-            .body(data)
-            .send().map_err(From::from)
-            .and_then(|mut r| match r.headers.get::<XErrorCode>().map(|v| v.0) {
-                None => {
-                    let mut out = String::new();
-                    r.read_to_string(&mut out).map_err(From::from).map(|_| out)
-                },
-                Some(code) => Err(PocketError::Proto(code, r.headers.get::<XError>().map(|v| &*v.0)
-                                                     .unwrap_or("unknown protocol error").to_string())),
-            })
-            .and_then(|s| json::decode::<Resp>(&*s).map_err(From::from))
+    fn require_access_token(&self) -> PocketResult<&str> {
+        self.access_token().ok_or(PocketError::NotAuthorized)
+    }
+
+    fn endpoint(&self, method: &str) -> PocketResult<Url> {
+        self.base_url.join(method).map_err(PocketError::InvalidUrl)
+    }
+
+    fn request<Resp: Decodable>(&mut self, method: &str, data: &str) -> PocketResult<Resp> {
+        self.post(method, data)
+            .and_then(|s| decoder::decode::<Resp>(&*s).map_err(From::from))
+    }
+
+    fn post(&mut self, method: &str, data: &str) -> PocketResult<String> {
+        let url = try!(self.endpoint(method));
+        self.transport.post(&url, data)
     }
 
     pub fn get_auth_url(&mut self) -> PocketResult<Url> {
+        self.get_auth_url_with(DEFAULT_REDIRECT_URI, None)
+    }
+
+    // Pocket redirects user to `redirect_uri` after access is confirmed,
+    // `state` is passed along with all OAuth requests to protect from CSRF
+    pub fn get_auth_url_with(&mut self, redirect_uri: &str, state: Option<&str>) -> PocketResult<Url> {
+        let token = try!(self.request_token(redirect_uri, state));
+        let url = self.auth_url(&token);
+        self.pending = Some(token);
+        Ok(url)
+    }
+
+    pub fn request_token(&mut self, redirect_uri: &str, state: Option<&str>) -> PocketResult<PocketRequestToken> {
         let request = try!(json::encode(&PocketOAuthRequest {
             consumer_key: &*self.consumer_key,
-            redirect_uri: "rustapi:finishauth",
-            state: None
+            redirect_uri: redirect_uri,
+            state: state
         }));
 
-        self.request("https://getpocket.com/v3/oauth/request", &*request)
-            .and_then(|r: PocketOAuthResponse| {
-                let mut url = Url::parse("https://getpocket.com/auth/authorize").unwrap();
-                url.set_query_from_pairs(vec![("request_token", &*r.code), ("redirect_uri", "rustapi:finishauth")].into_iter());
-                self.code = Some(r.code);
-                Ok(url)
-            })
+        self.request("oauth/request", &*request)
+            .and_then(|r: PocketOAuthResponse| r.into_token(redirect_uri, state))
+    }
+
+    pub fn auth_url(&self, token: &PocketRequestToken) -> Url {
+        token.auth_url(&self.authorize_url)
+    }
+
+    #[inline] pub fn pending_token(&self) -> Option<&PocketRequestToken> {
+        self.pending.as_ref()
+    }
+
+    #[inline] pub fn auth_state(&self) -> Option<&str> {
+        self.pending.as_ref().and_then(|v| v.state.as_ref()).map(|v| &**v)
     }
 
     pub fn authorize(&mut self) -> PocketResult<String> {
+        let token = try!(self.pending.clone().ok_or(PocketError::MissingRequestToken));
+        let username = try!(self.authorize_token(&token));
+        self.pending = None;
+        Ok(username)
+    }
+
+    pub fn authorize_token(&mut self, token: &PocketRequestToken) -> PocketResult<String> {
         let request = try!(json::encode(&PocketAuthorizeRequest {
             consumer_key: &*self.consumer_key,
-            code: self.code.as_ref().map(|v| &**v).unwrap()
+            code: &*token.code
         }));
 
-        match self.request("https://getpocket.com/v3/oauth/authorize", &*request)
-        {
-            Ok(r @ PocketAuthorizeResponse {..}) => {
-                self.access_token = Some(r.access_token);
-                Ok(r.username)
-            },
-            Err(e) => Err(e)
-        }
+        let r = try!(self.request("oauth/authorize", &*request)
+                     .and_then(|r: PocketAuthorizeResponse| r.verify(token)));
+        self.access_token = Some(r.access_token);
+        Ok(r.username)
     }
 
-    pub fn add<T: IntoUrl>(&mut self, url: T, title: Option<&str>, tags: Option<&str>, tweet_id: Option<&str>) -> PocketResult<PocketAddedItem> {
+    // Completes OAuth flow using local listener as redirect URI, `show_url` should open
+    // authorization URL in browser or ask user to do so, returns username and access token.
+    // Gives up if user doesn't confirm access in `timeout`.
+    pub fn authorize_loopback<F: FnOnce(&Url)>(&mut self, timeout: Duration, show_url: F) -> PocketResult<(String, String)> {
+        let listener = try!(LoopbackListener::bind());
+        let redirect_uri = try!(listener.redirect_uri());
+        let url = try!(self.get_auth_url_with(&*redirect_uri, Some(listener.state())));
+        show_url(&url);
+
+        try!(listener.wait(timeout));
+        let username = try!(self.authorize());
+        let access_token = try!(self.require_access_token()).to_string();
+        Ok((username, access_token))
+    }
+
+    pub fn add<T: IntoPocketUrl>(&mut self, url: T, title: Option<&str>, tags: Option<&str>, tweet_id: Option<&str>) -> PocketResult<PocketAddedItem> {
+        let url = try!(url.into_url().map_err(PocketError::InvalidUrl));
         let request = try!(json::encode(&PocketAddRequest {
             consumer_key: &*self.consumer_key,
-            access_token: &**self.access_token.as_ref().unwrap(),
-            url: &url.into_url().unwrap(),
+            access_token: try!(self.require_access_token()),
+            url: &url,
             title: title.map(|v| v.clone()),
             tags: tags.map(|v| v.clone()),
             tweet_id: tweet_id.map(|v| v.clone())
         }));
 
-        self.request("https://getpocket.com/v3/add", &*request)
+        self.request("add", &*request)
             .map(|v: PocketAddResponse| v.item)
     }
 
-    #[inline] pub fn push<T: IntoUrl>(&mut self, url: T) -> PocketResult<PocketAddedItem> {
+    pub fn send(&mut self, actions: &[&PocketAction]) -> PocketResult<Vec<PocketActionResult>> {
+        let request = try!(PocketSendRequest {
+            consumer_key: &*self.consumer_key,
+            access_token: try!(self.require_access_token()),
+            actions: actions
+        }.to_json());
+
+        self.post("send", &*request)
+            .and_then(|s| PocketSendResponse::from_str(&*s))
+            .map(|r| r.into_results(actions.iter().map(|a| (a.name(), a.item_id()))))
+    }
+
+    #[inline] pub fn push<T: IntoPocketUrl>(&mut self, url: T) -> PocketResult<PocketAddedItem> {
         self.add(url, None, None, None)
     }
 
@@ -979,23 +1708,423 @@ impl Pocket {
     }
 }
 
+#[cfg(test)]
+fn encode_json<T: JsonEncodable + ?Sized>(value: &T) -> String {
+    let mut out = String::new();
+    {
+        let mut encoder = json::Encoder::new(&mut out);
+        value.json_encode(&mut encoder).unwrap();
+    }
+    out
+}
+
 #[test]
 fn test_actions_serialize() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let time = Timespec::new(1400000000, 0);
+
+    let add_url = PocketAddAction::from_url(&url).title("Example").tags("a,b").ref_id("123456").at(time);
+    let add_id = PocketAddAction::from_item_id(229279689);
+    let archive = PocketArchiveAction::new(229279689).at(time);
+    let readd = PocketReaddAction::new(229279689);
+    let favorite = PocketFavoriteAction::new(229279689).at(time);
+    let unfavorite = PocketUnfavoriteAction::new(229279689);
+    let delete = PocketDeleteAction::new(229279689);
+    let tags_add = PocketTagsAddAction::new(229279689, "a,b").at(time);
+    let tags_replace = PocketTagsReplaceAction::new(229279689, "c");
+    let tags_clear = PocketTagsClearAction::new(229279689);
+    let tag_rename = PocketTagRenameAction::new("a", "b").at(time);
+
+    let actions: &[&PocketAction] = &[&add_url, &add_id, &archive, &readd, &favorite, &unfavorite,
+                                      &delete, &tags_add, &tags_replace, &tags_clear, &tag_rename];
+    let golden = include_str!("../tests/golden/actions.json");
+    for (action, expected) in actions.iter().zip(golden.lines()) {
+        assert_eq!(encode_json(*action), expected);
+    }
+    assert_eq!(actions.len(), golden.lines().count());
+
+    let request = PocketSendRequest {
+        consumer_key: "abc",
+        access_token: "def",
+        actions: &[&archive, &tags_add]
+    };
+    assert_eq!(encode_json(&request), include_str!("../tests/golden/send_request.json").trim_right());
+}
+
+#[test]
+fn test_requests_serialize() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let request = PocketAddRequest {
+        consumer_key: "abc",
+        access_token: "def",
+        url: &url,
+        title: Some("Example"),
+        tags: Some("a,b"),
+        tweet_id: None
+    };
+    assert_eq!(encode_json(&request), include_str!("../tests/golden/add_request.json").trim_right());
+
     let mut pocket = Pocket::new("abc", Some("def"));
-    let add_action = PocketAddAction {
-        item_id: None,
-        ref_id: None,
-        tags: None,
-        time: None,
-        title: None,
-        url: None
+    {
+        let mut request = pocket.filter();
+        request.search("rust").domain("example.com").tag(PocketGetTag::Tagged("a"))
+            .archived().articles().complete().favorite(true).since(Timespec::new(1400000000, 0))
+            .sort_by_newest().slice(10, 20);
+        assert_eq!(encode_json(&request), include_str!("../tests/golden/get_request.json").trim_right());
+    }
+
+    let request = pocket.filter();
+    assert_eq!(encode_json(&request), include_str!("../tests/golden/get_request_empty.json").trim_right());
+}
+
+#[test]
+fn test_send_response_decode() {
+    let response = PocketSendResponse::from_json(Json::from_str(r#"{
+        "status": 1,
+        "action_results": [true, false],
+        "action_errors": [null, {"message": "Invalid item id", "type": "Bad Request", "code": 422}]
+    }"#).unwrap()).unwrap();
+    assert_eq!(response.status, 1);
+    assert_eq!(response.action_results, vec![(true, None, None), (false, None, None)]);
+    assert_eq!(response.action_errors, vec![None, Some(PocketActionError {
+        message: "Invalid item id".to_string(),
+        error_type: "Bad Request".to_string(),
+        code: 422
+    })]);
+
+    let response = PocketSendResponse::from_json(Json::from_str(r#"{"status": 1, "action_results": [true]}"#).unwrap()).unwrap();
+    assert!(response.action_errors.is_empty());
+
+    assert!(PocketSendResponse::from_json(Json::from_str(r#"{"status": 1, "action_results": [1]}"#).unwrap()).is_err());
+
+    // broken added item is reported on its action only
+    let response = PocketSendResponse::from_json(Json::from_str(r#"{
+        "status": 1,
+        "action_results": [{"item_id": "229279689", "date_resolved": "yesterday"}, true]
+    }"#).unwrap()).unwrap();
+    assert_eq!(response.action_results[1], (true, None, None));
+    match response.action_results[0] {
+        (true, None, Some(ref e)) => assert_eq!(e.item_id, "229279689"),
+        ref other => panic!("unexpected result: {:?}", other)
+    }
+}
+
+#[test]
+fn test_unknown_enum_values() {
+    assert_eq!(PocketItemStatus::from(1), PocketItemStatus::Archived);
+    assert_eq!(PocketItemStatus::from(7), PocketItemStatus::Unknown(7));
+    assert_eq!(u8::from(PocketItemHas::Unknown(3)), 3);
+    assert_eq!(u8::from(PocketItemHas::Is), 2);
+
+    let response = PocketGetResponse::from_json(Json::from_str(include_str!("../tests/corpus/get_odd_values.json")).unwrap()).unwrap();
+    assert_eq!((response.status, response.complete, response.since), (1, true, Timespec::new(1445029281, 0)));
+    assert_eq!(response.search_meta, Some(PocketSearchMeta {
+        search_type: "normal".to_string(),
+        total_result_count: None,
+        count: None,
+        offset: None,
+        has_more: None
+    }));
+    // deleted items are reported by item_id only
+    assert_eq!(response.deleted, vec![229279691]);
+    let mut items = response.list;
+    items.sort_by(|a, b| a.item_id.cmp(&b.item_id));
+    assert_eq!(items.iter().map(|v| v.status).collect::<Vec<_>>(),
+               vec![PocketItemStatus::Unknown(3), PocketItemStatus::Archived]);
+    assert_eq!(items.iter().map(|v| (v.has_image, v.has_video)).collect::<Vec<_>>(),
+               vec![(PocketItemHas::Unknown(4), PocketItemHas::No),
+                    (PocketItemHas::Yes, PocketItemHas::Unknown(9))]);
+
+    assert_eq!(items[0].images.as_ref().map(|v| v.len()), Some(1));
+    assert_eq!(items[1].videos.as_ref().map(|v| v[0].vid.clone()), Some("UtVdJM6xmmI".to_string()));
+
+    let response: PocketAddResponse = decoder::decode(include_str!("../tests/corpus/add_odd_values.json")).unwrap();
+    assert_eq!(response.item.has_image, PocketItemHas::Unknown(5));
+    assert_eq!(response.item.has_video, PocketItemHas::Unknown(255));
+}
+
+#[test]
+fn test_lenient_decode() {
+    let response = PocketGetResponse::from_json(Json::from_str(include_str!("../tests/corpus/get_lenient_values.json")).unwrap()).unwrap();
+    assert_eq!((response.status, response.complete, response.error), (1, true, None));
+
+    let item = &response.list[0];
+    assert_eq!((item.item_id, item.resolved_id, item.word_count, item.sort_id), (229279689, 0, 42, 0));
+    assert_eq!(item.given_title, "12345");
+    assert_eq!((item.favorite, item.is_article, item.is_index), (false, true, false));
+    assert_eq!((item.has_image, item.has_video), (PocketItemHas::Yes, PocketItemHas::No));
+    assert_eq!((item.time_updated, item.time_read, item.time_favorited),
+               (Some(Timespec::new(1445029002, 0)), None, None));
+    assert!(!item.is_read() && !item.is_favorited() && !item.is_archived());
+    assert_eq!(item.videos.as_ref().map(|v| (v[0].width, v[0].length, v[0].vtype)), Some((0, None, 1)));
+
+    let response = PocketGetResponse::from_json(Json::from_str(r#"{"status": 2, "complete": 1, "list": [], "since": 1445029281}"#).unwrap()).unwrap();
+    assert!(response.list.is_empty());
+
+    assert!(decoder::decode::<u8>(r#""256""#).is_err());
+    assert!(decoder::decode::<u64>(r#""abc""#).is_err());
+    assert!(decoder::decode::<bool>("null").is_err());
+    assert_eq!(decoder::decode::<i32>(r#""-5""#).unwrap(), -5);
+    assert_eq!(decoder::decode::<u64>("4e3").unwrap(), 4000);
+    assert!(decoder::decode::<u64>("1e30").is_err());
+    assert!(decoder::decode::<u64>(r#""18446744073709551616""#).is_err());
+    assert!(decoder::decode::<i64>("-1e30").is_err());
+}
+
+#[test]
+fn test_get_decode_errors() {
+    let mut pocket = Pocket::new("abc", Some("def"));
+    let request = json::encode(&pocket.filter()).unwrap();
+    let exchange = PocketExchange {
+        url: "https://getpocket.com/v3/get".to_string(),
+        request: request,
+        response: include_str!("../tests/corpus/get_broken_items.json").to_string(),
+        error_code: None,
+        error: None
     };
-    let act: &PocketAction = &add_action;
-    let actions = PocketSendRequest {
-        pocket: &mut pocket,
-        actions: &[act]
+    pocket.set_transport(ReplayTransport::new(vec![exchange.clone(), exchange]));
+
+    match pocket.filter().get() {
+        Err(PocketError::Decode(ref e)) if e.item_id == "1002" && e.field == Some("given_url".to_string()) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+
+    let (items, errors) = pocket.filter().get_lenient().unwrap();
+    assert_eq!(items.iter().map(|v| v.item_id).collect::<Vec<_>>(), vec![1001]);
+    assert_eq!(errors.iter().map(|e| (&*e.item_id, e.field.as_ref().map(|v| &**v))).collect::<Vec<_>>(),
+               vec![("1002", Some("given_url")), ("1003", Some("images.2.src"))]);
+    assert_eq!(errors[1].error, json::DecoderError::MissingFieldError("src".to_string()));
+}
+
+#[test]
+fn test_item_tags_authors() {
+    let response = PocketGetResponse::from_json(Json::from_str(include_str!("../tests/corpus/get_complete_item.json")).unwrap()).unwrap();
+    let item = &response.list[0];
+    assert_eq!(item.tags, vec![
+        ItemTag { item_id: 229279689, tag: "golf".to_string() },
+        ItemTag { item_id: 229279689, tag: "sports".to_string() }
+    ]);
+    assert_eq!(item.authors, vec![ItemAuthor {
+        item_id: 229279689,
+        author_id: 33475,
+        name: "Bill Barnwell".to_string(),
+        url: "http://grantland.com/contributors/bill-barnwell/".to_string()
+    }]);
+
+    let response = PocketGetResponse::from_json(Json::from_str(include_str!("../tests/corpus/get_odd_values.json")).unwrap()).unwrap();
+    assert!(response.list.iter().all(|v| v.tags.is_empty() && v.authors.is_empty()));
+
+    let response: PocketAddResponse = decoder::decode(include_str!("../tests/corpus/add_odd_values.json")).unwrap();
+    assert!(response.item.tags.is_empty() && response.item.authors.is_empty());
+}
+
+#[test]
+fn test_added_item_dates_mime() {
+    let response: PocketAddResponse = decoder::decode(include_str!("../tests/corpus/add_odd_values.json")).unwrap();
+    assert_eq!(response.item.date_published, None);
+    assert_eq!(response.item.date_resolved, Some(Timespec::new(1445029375, 0)));
+    assert_eq!(response.item.mime_type, Some("text/html".parse().unwrap()));
+
+    let mut item = Json::from_str(include_str!("../tests/corpus/add_odd_values.json")).unwrap()["item"].clone();
+    if let Json::Object(ref mut obj) = item {
+        obj.insert("mime_type".to_string(), Json::String("".to_string()));
+        obj.insert("date_resolved".to_string(), Json::String("2015-10-16".to_string()));
+    }
+    match decode_json::<PocketAddedItem>(item) {
+        Err(json::DecoderError::ApplicationError(ref e)) if e == "invalid date: 2015-10-16" => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
+
+#[test]
+fn test_item_timestamps() {
+    let response = PocketGetResponse::from_json(Json::from_str(include_str!("../tests/corpus/get_complete_item.json")).unwrap()).unwrap();
+    let item = &response.list[0];
+    assert_eq!((item.time_added, item.time_read, item.time_favorited),
+               (Some(Timespec::new(1473082271, 0)), Some(Timespec::new(1473082300, 0)), Some(Timespec::new(1473082295, 0))));
+    assert!(item.is_read() && item.is_favorited() && item.is_archived() && !item.is_deleted());
+
+    let response = PocketGetResponse::from_json(Json::from_str(include_str!("../tests/corpus/get_odd_values.json")).unwrap()).unwrap();
+    assert_eq!(response.list.iter().map(|v| (v.is_read(), v.is_favorited(), v.is_deleted())).collect::<Vec<_>>(),
+               vec![(false, false, false), (true, true, false)]);
+}
+
+#[test]
+fn test_item_extra_fields() {
+    let response = PocketGetResponse::from_json(Json::from_str(include_str!("../tests/corpus/get_complete_item.json")).unwrap()).unwrap();
+    let item = &response.list[0];
+    assert_eq!(item.lang, Some("en".to_string()));
+    assert_eq!((item.time_to_read, item.listen_duration_estimate), (Some(15), Some(1238)));
+    assert_eq!(item.top_image_url, Some(Url::parse("http://a.espncdn.com/photo/2012/0927/grant_g_ryder_cr_640.jpg").unwrap()));
+    assert_eq!(item.amp_url, Some(Url::parse("http://www.grantland.com/amp/38347").unwrap()));
+    assert_eq!(item.domain_metadata, Some(ItemDomainMetadata {
+        name: Some("Grantland".to_string()),
+        logo: Some(Url::parse("https://logo.clearbit.com/grantland.com?size=800").unwrap()),
+        greyscale_logo: Some(Url::parse("https://logo.clearbit.com/grantland.com?size=800&greyscale=true").unwrap())
+    }));
+
+    let response = PocketGetResponse::from_json(Json::from_str(include_str!("../tests/corpus/get_odd_values.json")).unwrap()).unwrap();
+    assert!(response.list.iter().all(|v| v.lang.is_none() && v.time_to_read.is_none() && v.domain_metadata.is_none()));
+
+    let item: PocketItem = decoder::decode(r#"{"item_id": "1", "resolved_id": "1", "given_url": "http://example.com/",
+        "given_title": "", "resolved_title": "", "resolved_url": "http://example.com/", "excerpt": "", "word_count": "0",
+        "favorite": "0", "status": "0", "time_added": "0", "time_updated": "0", "time_read": "0", "time_favorited": "0",
+        "sort_id": 0, "is_article": "0", "is_index": "0", "has_video": "0", "has_image": "0",
+        "lang": "", "time_to_read": null, "top_image_url": "", "domain_metadata": {"name": "Example"}}"#).unwrap();
+    assert_eq!((item.lang, item.time_to_read, item.top_image_url), (None, None, None));
+    assert_eq!(item.domain_metadata.and_then(|v| v.name), Some("Example".to_string()));
+}
+
+#[test]
+fn test_actions_construct() {
+    let url = Url::parse("http://example.com/").unwrap();
+    let actions: [&PocketAction; 3] = [
+        &PocketArchiveAction::new(123).at(Timespec::new(1400000000, 0)),
+        &PocketAddAction::from_url(&url).title("Example").tags("a,b"),
+        &PocketTagsReplaceAction::new(123, "c")
+    ];
+
+    assert_eq!(encode_json(actions[0]), r#"{"name":"archive","item_id":123,"time":1400000000}"#);
+    assert_eq!(actions[1].name(), "add");
+    assert_eq!(actions[2].name(), "tags_replace");
+}
+
+#[test]
+fn test_endpoint_urls() {
+    let pocket = Pocket::new("abc", None);
+    assert_eq!(pocket.endpoint("oauth/request").unwrap().to_string(), "https://getpocket.com/v3/oauth/request");
+
+    let pocket = Pocket::with_urls("abc", None,
+                                   Url::parse("http://127.0.0.1:8080/v3/").unwrap(),
+                                   Url::parse("http://127.0.0.1:8080/auth/authorize").unwrap());
+    assert_eq!(pocket.endpoint("get").unwrap().to_string(), "http://127.0.0.1:8080/v3/get");
+}
+
+#[test]
+fn test_replay_transport() {
+    let mut pocket = Pocket::new("abc", None);
+    pocket.set_transport(ReplayTransport::new(vec![
+        PocketExchange {
+            url: "https://getpocket.com/v3/oauth/request".to_string(),
+            request: r#"{"consumer_key":"abc","redirect_uri":"rustapi:finishauth","state":null}"#.to_string(),
+            response: r#"{"code":"dcba4321-dcba-4321-dcba-4321dc","state":null}"#.to_string(),
+            error_code: None,
+            error: None
+        },
+        PocketExchange {
+            url: "https://getpocket.com/v3/oauth/authorize".to_string(),
+            request: r#"{"consumer_key":"abc","code":"dcba4321-dcba-4321-dcba-4321dc"}"#.to_string(),
+            response: String::new(),
+            error_code: Some(158),
+            error: Some("User rejected code.".to_string())
+        }
+    ]));
+
+    let url = pocket.get_auth_url().unwrap();
+    assert_eq!(url.query_pairs().unwrap()[0], ("request_token".to_string(), "dcba4321-dcba-4321-dcba-4321dc".to_string()));
+    match pocket.authorize() {
+        Err(PocketError::Proto(158, ref msg)) if msg == "User rejected code." => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    match pocket.authorize() {
+        Err(PocketError::Io(_)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
+
+#[test]
+fn test_auth_state_mismatch() {
+    let mut pocket = Pocket::new("abc", None);
+    pocket.set_transport(ReplayTransport::new(vec![
+        PocketExchange {
+            url: "https://getpocket.com/v3/oauth/request".to_string(),
+            request: r#"{"consumer_key":"abc","redirect_uri":"https://example.com/callback","state":"xyz"}"#.to_string(),
+            response: r#"{"code":"dcba4321-dcba-4321-dcba-4321dc","state":"forged"}"#.to_string(),
+            error_code: None,
+            error: None
+        }
+    ]));
+
+    match pocket.get_auth_url_with("https://example.com/callback", Some("xyz")) {
+        Err(PocketError::StateMismatch) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    assert_eq!(pocket.auth_state(), None);
+
+    pocket.set_transport(ReplayTransport::new(vec![
+        PocketExchange {
+            url: "https://getpocket.com/v3/oauth/request".to_string(),
+            request: r#"{"consumer_key":"abc","redirect_uri":"https://example.com/callback","state":"xyz"}"#.to_string(),
+            response: r#"{"code":"dcba4321-dcba-4321-dcba-4321dc"}"#.to_string(),
+            error_code: None,
+            error: None
+        }
+    ]));
+
+    match pocket.get_auth_url_with("https://example.com/callback", Some("xyz")) {
+        Err(PocketError::StateMismatch) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+
+    let token = PocketRequestToken {
+        code: "dcba4321-dcba-4321-dcba-4321dc".to_string(),
+        redirect_uri: "https://example.com/callback".to_string(),
+        state: Some("xyz".to_string())
+    };
+    let authorize = |response: &str| PocketExchange {
+        url: "https://getpocket.com/v3/oauth/authorize".to_string(),
+        request: r#"{"consumer_key":"abc","code":"dcba4321-dcba-4321-dcba-4321dc"}"#.to_string(),
+        response: response.to_string(),
+        error_code: None,
+        error: None
     };
-    //assert_eq!(&*actions.to_json().to_string(), "{
+    pocket.set_transport(ReplayTransport::new(vec![
+        authorize(r#"{"access_token":"5678defg","username":"pocketuser","state":"forged"}"#),
+        authorize(r#"{"access_token":"5678defg","username":"pocketuser"}"#)
+    ]));
 
-    //}");
+    match pocket.authorize_token(&token) {
+        Err(PocketError::StateMismatch) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    assert_eq!(pocket.authorize_token(&token).unwrap(), "pocketuser");
+}
+
+#[test]
+fn test_authorize_without_request_token() {
+    let mut pocket = Pocket::new("abc", None);
+    match pocket.authorize() {
+        Err(PocketError::MissingRequestToken) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
+
+#[test]
+fn test_not_authorized() {
+    let mut pocket = Pocket::new("abc", None);
+    pocket.set_transport(ReplayTransport::new(vec![]));
+
+    match pocket.push("http://example.com/") {
+        Err(PocketError::NotAuthorized) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    match pocket.filter().get() {
+        Err(PocketError::NotAuthorized) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    match pocket.send(&[&PocketArchiveAction::new(1)]) {
+        Err(PocketError::NotAuthorized) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
+
+#[test]
+fn test_add_invalid_url() {
+    let mut pocket = Pocket::new("abc", Some("def"));
+    pocket.set_transport(ReplayTransport::new(vec![]));
+
+    match pocket.push("not a url") {
+        Err(PocketError::InvalidUrl(_)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
 }
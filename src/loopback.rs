@@ -0,0 +1,121 @@
+use getrandom::getrandom;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::thread;
+use std::time::{Duration, Instant};
+use url::form_urlencoded;
+
+use super::PocketResult;
+
+const CALLBACK_PATH: &'static str = "/pocket/callback";
+
+// HTTP listener on a random port of 127.0.0.1 used as OAuth `redirect_uri` by CLI apps,
+// waits for Pocket to redirect user's browser back after access is confirmed
+pub struct LoopbackListener {
+    listener: TcpListener,
+    state: String
+}
+
+impl LoopbackListener {
+    pub fn bind() -> PocketResult<LoopbackListener> {
+        let listener = try!(TcpListener::bind("127.0.0.1:0"));
+        Ok(LoopbackListener {
+            listener: listener,
+            state: try!(random_state())
+        })
+    }
+
+    // Random state is a part of redirect URI, so requests not coming from Pocket redirect are ignored
+    pub fn redirect_uri(&self) -> PocketResult<String> {
+        let addr = try!(self.listener.local_addr());
+        Ok(format!("http://{}{}?state={}", addr, CALLBACK_PATH, self.state))
+    }
+
+    pub fn state(&self) -> &str {
+        &*self.state
+    }
+
+    // Blocks until callback request is received, other requests (e.g. favicon) are answered with 404.
+    // Fails with `TimedOut` I/O error if there's no callback in `timeout`, e.g. user closed the page.
+    pub fn wait(&self, timeout: Duration) -> PocketResult<()> {
+        let deadline = Instant::now() + timeout;
+        try!(self.listener.set_nonblocking(true));
+
+        loop {
+            if Instant::now() >= deadline {
+                return Err(From::from(io::Error::new(io::ErrorKind::TimedOut, "no OAuth callback received in time")));
+            }
+
+            let mut stream = match self.listener.accept() {
+                Ok((stream, _)) => stream,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(50));
+                    continue;
+                },
+                Err(e) => return Err(From::from(e))
+            };
+
+            try!(stream.set_nonblocking(false));
+            let read_deadline = ::std::cmp::min(deadline, Instant::now() + Duration::from_secs(5));
+            let found = read_target(&stream, read_deadline).map(|t| self.is_callback(&*t)).unwrap_or(false);
+            let _ = if found {
+                respond(&mut stream, "200 OK", "Pocket access confirmed, you can close this page now.")
+            } else {
+                respond(&mut stream, "404 Not Found", "Not found.")
+            };
+            if found {
+                return Ok(());
+            }
+        }
+    }
+
+    // Other query parameters are allowed, as long as state is ours
+    fn is_callback(&self, target: &str) -> bool {
+        let (path, query) = match target.find('?') {
+            Some(pos) => (&target[..pos], &target[pos + 1..]),
+            None => (target, "")
+        };
+        path == CALLBACK_PATH && form_urlencoded::parse(query.as_bytes()).iter().any(|&(ref k, ref v)| k == "state" && *v == self.state)
+    }
+}
+
+// State protects from CSRF, so it comes from OS random source
+fn random_state() -> PocketResult<String> {
+    let mut bytes = [0u8; 16];
+    try!(getrandom(&mut bytes).map_err(io::Error::from));
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// Reads request head and returns request target, browsers may open idle connections,
+// so reads time out at `deadline` instead of blocking the listener
+fn read_target(stream: &TcpStream, deadline: Instant) -> Option<String> {
+    let mut reader = BufReader::new(stream);
+    let mut read_line = |line: &mut String| {
+        let now = Instant::now();
+        if now >= deadline || stream.set_read_timeout(Some(deadline - now)).is_err() {
+            return false;
+        }
+        reader.read_line(line).map(|n| n > 0).unwrap_or(false)
+    };
+
+    let mut line = String::new();
+    if !read_line(&mut line) {
+        return None;
+    }
+    let target = line.split(' ').nth(1).map(|v| v.to_string());
+
+    loop {
+        let mut header = String::new();
+        if !read_line(&mut header) || header.trim().is_empty() {
+            break;
+        }
+    }
+    target
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) -> PocketResult<()> {
+    try!(write!(stream, "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status, body.len(), body));
+    try!(stream.flush());
+    Ok(())
+}
@@ -0,0 +1,667 @@
+use url::{Url, form_urlencoded};
+use rustc_serialize::json::{Json, ToJson};
+use std::collections::BTreeMap;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use time::get_time;
+
+use super::{PocketResult, PocketItemStatus};
+#[cfg(feature = "hyper")]
+use super::Pocket;
+#[cfg(feature = "async")]
+use super::PocketAsync;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockItem {
+    pub item_id: u64,
+    pub url: String,
+    pub title: String,
+    pub excerpt: String,
+    pub word_count: usize,
+    pub tags: Vec<String>,
+    pub favorite: bool,
+    pub status: PocketItemStatus,
+    pub time_added: i64,
+    pub time_updated: i64,
+    pub time_read: i64,
+    pub time_favorited: i64,
+}
+
+impl MockItem {
+    fn domain(&self) -> Option<String> {
+        Url::parse(&*self.url).ok().and_then(|u| u.domain().map(|d| d.to_string()))
+    }
+
+    // Item as returned in `/v3/get` list
+    fn to_item_json(&self, sort_id: usize) -> Json {
+        let mut obj = BTreeMap::new();
+        obj.insert("item_id".to_string(), self.item_id.to_string().to_json());
+        // deleted items are sent as stubs, just like Pocket does
+        if self.status == PocketItemStatus::Deleted {
+            obj.insert("status".to_string(), "2".to_json());
+            return Json::Object(obj);
+        }
+        obj.insert("resolved_id".to_string(), self.item_id.to_string().to_json());
+        obj.insert("given_url".to_string(), self.url.to_json());
+        obj.insert("given_title".to_string(), self.title.to_json());
+        obj.insert("resolved_url".to_string(), self.url.to_json());
+        obj.insert("resolved_title".to_string(), self.title.to_json());
+        obj.insert("excerpt".to_string(), self.excerpt.to_json());
+        obj.insert("word_count".to_string(), self.word_count.to_string().to_json());
+        obj.insert("favorite".to_string(), flag(self.favorite));
+        obj.insert("status".to_string(), u8::from(self.status).to_string().to_json());
+        obj.insert("time_added".to_string(), self.time_added.to_string().to_json());
+        obj.insert("time_updated".to_string(), self.time_updated.to_string().to_json());
+        obj.insert("time_read".to_string(), self.time_read.to_string().to_json());
+        obj.insert("time_favorited".to_string(), self.time_favorited.to_string().to_json());
+        obj.insert("is_article".to_string(), flag(true));
+        obj.insert("is_index".to_string(), flag(false));
+        obj.insert("has_image".to_string(), flag(false));
+        obj.insert("has_video".to_string(), flag(false));
+        obj.insert("sort_id".to_string(), sort_id.to_json());
+        if !self.tags.is_empty() {
+            obj.insert("tags".to_string(), self.to_tags_json());
+        }
+        Json::Object(obj)
+    }
+
+    fn to_tags_json(&self) -> Json {
+        Json::Object(self.tags.iter().map(|tag| {
+            let mut t = BTreeMap::new();
+            t.insert("item_id".to_string(), self.item_id.to_string().to_json());
+            t.insert("tag".to_string(), tag.to_json());
+            (tag.clone(), Json::Object(t))
+        }).collect())
+    }
+
+    // Item as returned by `/v3/add` and `add` action
+    fn to_added_json(&self) -> Json {
+        let mut obj = BTreeMap::new();
+        for key in &["item_id", "extended_item_id", "resolved_id"] {
+            obj.insert(key.to_string(), self.item_id.to_string().to_json());
+        }
+        for key in &["given_url", "normal_url", "resolved_url", "resolved_normal_url"] {
+            obj.insert(key.to_string(), self.url.to_json());
+        }
+        obj.insert("title".to_string(), self.title.to_json());
+        obj.insert("excerpt".to_string(), self.excerpt.to_json());
+        obj.insert("content_length".to_string(), "0".to_json());
+        obj.insert("word_count".to_string(), self.word_count.to_string().to_json());
+        obj.insert("encoding".to_string(), "utf-8".to_json());
+        obj.insert("mime_type".to_string(), "text/html".to_json());
+        obj.insert("lang".to_string(), "en".to_json());
+        obj.insert("date_published".to_string(), "0000-00-00 00:00:00".to_json());
+        obj.insert("date_resolved".to_string(), "0000-00-00 00:00:00".to_json());
+        obj.insert("login_required".to_string(), flag(false));
+        obj.insert("response_code".to_string(), "200".to_json());
+        obj.insert("used_fallback".to_string(), flag(false));
+        obj.insert("domain_id".to_string(), "0".to_json());
+        obj.insert("origin_domain_id".to_string(), "0".to_json());
+        obj.insert("innerdomain_redirect".to_string(), flag(false));
+        obj.insert("is_index".to_string(), flag(false));
+        obj.insert("is_article".to_string(), flag(true));
+        obj.insert("has_image".to_string(), flag(false));
+        obj.insert("has_video".to_string(), flag(false));
+        obj.insert("tags".to_string(), if self.tags.is_empty() { Json::Array(vec![]) } else { self.to_tags_json() });
+        obj.insert("authors".to_string(), Json::Array(vec![]));
+        obj.insert("videos".to_string(), Json::Array(vec![]));
+        obj.insert("images".to_string(), Json::Array(vec![]));
+        Json::Object(obj)
+    }
+}
+
+fn flag(v: bool) -> Json {
+    (if v { "1" } else { "0" }).to_json()
+}
+
+fn object(pairs: Vec<(&str, Json)>) -> Json {
+    Json::Object(pairs.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+}
+
+// Pocket clients send numbers either as JSON numbers or as strings
+fn find_u64(json: &Json, key: &str) -> Option<u64> {
+    json.find(key).and_then(|v| v.as_u64().or_else(|| v.as_string().and_then(|s| s.parse().ok())))
+}
+
+fn find_str<'a>(json: &'a Json, key: &str) -> Option<&'a str> {
+    json.find(key).and_then(|v| v.as_string())
+}
+
+fn find_bool(json: &Json, key: &str) -> Option<bool> {
+    json.find(key).and_then(|v| v.as_boolean().or_else(|| find_u64(json, key).map(|v| v != 0)))
+}
+
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split(',').map(|t| t.trim()).filter(|t| !t.is_empty()).map(|t| t.to_string()).collect()
+}
+
+enum Reply {
+    Json(Json),
+    Redirect(String),
+    Error(u16, u16, String)
+}
+
+struct RequestToken {
+    redirect_uri: String,
+    state: Option<String>,
+    approved: bool
+}
+
+struct MockState {
+    consumer_key: String,
+    access_token: String,
+    username: String,
+    request_tokens: BTreeMap<String, RequestToken>,
+    next_token: u64,
+    next_item_id: u64,
+    items: BTreeMap<u64, MockItem>,
+    failure: Option<(u16, u16, String)>
+}
+
+impl MockState {
+    fn insert(&mut self, url: &str, title: &str, now: i64) -> u64 {
+        if let Some(item) = self.items.values_mut().find(|item| item.url == url) {
+            // Pocket re-adds already saved URLs instead of duplicating them
+            item.status = PocketItemStatus::Normal;
+            item.time_updated = now;
+            return item.item_id;
+        }
+
+        let item_id = self.next_item_id;
+        self.next_item_id += 1;
+        self.items.insert(item_id, MockItem {
+            item_id: item_id,
+            url: url.to_string(),
+            title: title.to_string(),
+            excerpt: String::new(),
+            word_count: 0,
+            tags: Vec::new(),
+            favorite: false,
+            status: PocketItemStatus::Normal,
+            time_added: now,
+            time_updated: now,
+            time_read: 0,
+            time_favorited: 0
+        });
+        item_id
+    }
+
+    fn authorize_page(&mut self, query: &str) -> Reply {
+        let params = form_urlencoded::parse(query.as_bytes());
+        let token = params.iter().find(|p| p.0 == "request_token").map(|p| &*p.1).unwrap_or("");
+        match self.request_tokens.get_mut(token) {
+            Some(request) => {
+                request.approved = true;
+                Reply::Redirect(request.redirect_uri.clone())
+            },
+            None => Reply::Error(400, 185, "Code not found.".to_string())
+        }
+    }
+
+    fn api(&mut self, path: &str, body: &str) -> Reply {
+        if let Some((status, code, message)) = self.failure.take() {
+            return Reply::Error(status, code, message);
+        }
+
+        let request = match Json::from_str(body) {
+            Ok(json @ Json::Object(_)) => json,
+            _ => return Reply::Error(400, 0, "Invalid request body.".to_string())
+        };
+
+        match find_str(&request, "consumer_key") {
+            None => return Reply::Error(400, 138, "Missing consumer key.".to_string()),
+            Some(key) if key != self.consumer_key => return Reply::Error(403, 152, "Invalid consumer key.".to_string()),
+            _ => ()
+        }
+
+        match path {
+            "/v3/oauth/request" => self.oauth_request(&request),
+            "/v3/oauth/authorize" => self.oauth_authorize(&request),
+            "/v3/add" | "/v3/get" | "/v3/send" => {
+                if find_str(&request, "access_token") != Some(&*self.access_token) {
+                    return Reply::Error(401, 107, "Consumer key/access token mismatch.".to_string());
+                }
+                match path {
+                    "/v3/add" => self.add(&request),
+                    "/v3/get" => self.get(&request),
+                    _ => self.send(&request)
+                }
+            },
+            _ => Reply::Error(404, 0, "Unknown API method.".to_string())
+        }
+    }
+
+    fn oauth_request(&mut self, request: &Json) -> Reply {
+        let redirect_uri = match find_str(request, "redirect_uri") {
+            Some(uri) => uri.to_string(),
+            None => return Reply::Error(400, 140, "Missing redirect url.".to_string())
+        };
+        let state = find_str(request, "state").map(|v| v.to_string());
+
+        self.next_token += 1;
+        let code = format!("mock-request-token-{}", self.next_token);
+        self.request_tokens.insert(code.clone(), RequestToken {
+            redirect_uri: redirect_uri,
+            state: state.clone(),
+            approved: false
+        });
+
+        Reply::Json(object(vec![("code", code.to_json()), ("state", state.to_json())]))
+    }
+
+    fn oauth_authorize(&mut self, request: &Json) -> Reply {
+        let code = match find_str(request, "code") {
+            Some(code) => code.to_string(),
+            None => return Reply::Error(400, 182, "Missing code.".to_string())
+        };
+
+        match self.request_tokens.get(&code).map(|token| token.approved) {
+            None => return Reply::Error(400, 185, "Code not found.".to_string()),
+            Some(false) => return Reply::Error(403, 158, "User rejected code.".to_string()),
+            Some(true) => ()
+        }
+
+        let token = self.request_tokens.remove(&code).unwrap();
+        Reply::Json(object(vec![
+            ("access_token", self.access_token.to_json()),
+            ("username", self.username.to_json()),
+            ("state", token.state.to_json())
+        ]))
+    }
+
+    fn add(&mut self, request: &Json) -> Reply {
+        let url = match find_str(request, "url").and_then(|u| Url::parse(u).ok()) {
+            Some(url) => url.serialize(),
+            None => return Reply::Error(400, 0, "Invalid URL.".to_string())
+        };
+        let item_id = self.insert(&*url, find_str(request, "title").unwrap_or(""), get_time().sec);
+        let item = self.items.get_mut(&item_id).unwrap();
+        if let Some(tags) = find_str(request, "tags") {
+            for tag in split_tags(tags) {
+                if !item.tags.contains(&tag) {
+                    item.tags.push(tag);
+                }
+            }
+        }
+
+        Reply::Json(object(vec![("item", item.to_added_json()), ("status", 1.to_json())]))
+    }
+
+    fn get(&mut self, request: &Json) -> Reply {
+        let since = find_u64(request, "since").map(|v| v as i64);
+        let state = find_str(request, "state").unwrap_or("unread");
+        let favorite = find_bool(request, "favorite");
+        let tag = find_str(request, "tag");
+        let content_type = find_str(request, "content_type");
+        let search = find_str(request, "search").map(|s| s.to_lowercase());
+        let domain = find_str(request, "domain");
+
+        let mut items: Vec<&MockItem> = self.items.values().filter(|item| {
+            // deleted items are reported in `since` queries only, so clients can sync removals
+            match (item.status, state) {
+                (PocketItemStatus::Deleted, _) => since.is_some(),
+                (_, "all") => true,
+                (PocketItemStatus::Archived, "archive") => true,
+                (PocketItemStatus::Normal, "unread") => true,
+                _ => false
+            }
+        }).filter(|item| {
+            since.map_or(true, |since| item.time_updated >= since) &&
+            favorite.map_or(true, |fav| item.favorite == fav) &&
+            tag.map_or(true, |tag| match tag {
+                "_untagged_" => item.tags.is_empty(),
+                tag => item.tags.iter().any(|t| t == tag)
+            }) &&
+            // mock items have no media, so they are all articles
+            content_type.map_or(true, |ct| ct == "article") &&
+            search.as_ref().map_or(true, |s| item.title.to_lowercase().contains(&**s) ||
+                                             item.url.to_lowercase().contains(&**s)) &&
+            domain.map_or(true, |d| item.domain().map_or(false, |h| h == d || h.ends_with(&*format!(".{}", d))))
+        }).collect();
+
+        match find_str(request, "sort").unwrap_or("newest") {
+            "oldest" => items.sort_by(|a, b| (a.time_added, a.item_id).cmp(&(b.time_added, b.item_id))),
+            "title" => items.sort_by(|a, b| a.title.cmp(&b.title)),
+            "site" => items.sort_by(|a, b| a.url.cmp(&b.url)),
+            _ => items.sort_by(|a, b| (b.time_added, b.item_id).cmp(&(a.time_added, a.item_id)))
+        }
+
+        let total = items.len();
+        let offset = find_u64(request, "offset").unwrap_or(0) as usize;
+        let count = find_u64(request, "count").map(|v| v as usize).unwrap_or(total);
+        let list: BTreeMap<String, Json> = items.into_iter().skip(offset).take(count).enumerate()
+            .map(|(sort_id, item)| (item.item_id.to_string(), item.to_item_json(sort_id)))
+            .collect();
+
+        Reply::Json(object(vec![
+            ("status", (if list.is_empty() { 2 } else { 1 }).to_json()),
+            ("complete", 1.to_json()),
+            ("error", Json::Null),
+            ("since", get_time().sec.to_json()),
+            ("search_meta", object(vec![
+                ("search_type", "normal".to_json()),
+                ("total_result_count", total.to_json()),
+                ("count", count.to_json()),
+                ("offset", offset.to_json()),
+                ("has_more", (offset + count < total).to_json())
+            ])),
+            // empty list is encoded as an empty array, just like Pocket does
+            ("list", if list.is_empty() { Json::Array(vec![]) } else { Json::Object(list) })
+        ]))
+    }
+
+    fn send(&mut self, request: &Json) -> Reply {
+        let actions = match request.find("actions").and_then(|v| v.as_array()) {
+            Some(actions) => actions,
+            None => return Reply::Error(400, 0, "Missing actions.".to_string())
+        };
+
+        let mut results = Vec::new();
+        let mut errors = Vec::new();
+        for action in actions.iter() {
+            match self.apply(action) {
+                Ok(result) => {
+                    results.push(result);
+                    errors.push(Json::Null);
+                },
+                Err((code, kind, message)) => {
+                    results.push(Json::Boolean(false));
+                    errors.push(object(vec![
+                        ("message", message.to_json()),
+                        ("type", kind.to_json()),
+                        ("code", code.to_json())
+                    ]));
+                }
+            }
+        }
+
+        Reply::Json(object(vec![
+            ("status", 1.to_json()),
+            ("action_results", Json::Array(results)),
+            ("action_errors", Json::Array(errors))
+        ]))
+    }
+
+    fn apply(&mut self, action: &Json) -> Result<Json, (u16, &'static str, String)> {
+        let now = find_u64(action, "time").map(|v| v as i64).unwrap_or_else(|| get_time().sec);
+        let name = find_str(action, "name").unwrap_or("");
+
+        match name {
+            "add" => {
+                let item_id = match (find_u64(action, "item_id"), find_str(action, "url")) {
+                    (Some(item_id), _) if self.items.contains_key(&item_id) => {
+                        let item = self.items.get_mut(&item_id).unwrap();
+                        item.status = PocketItemStatus::Normal;
+                        item.time_updated = now;
+                        item_id
+                    },
+                    (_, Some(url)) if Url::parse(url).is_ok() => {
+                        let url = Url::parse(url).unwrap().serialize();
+                        self.insert(&*url, find_str(action, "title").unwrap_or(""), now)
+                    },
+                    _ => return Err((422, "Unprocessable Entity", "Invalid item or url.".to_string()))
+                };
+                let item = self.items.get_mut(&item_id).unwrap();
+                if let Some(tags) = find_str(action, "tags") {
+                    for tag in split_tags(tags) {
+                        if !item.tags.contains(&tag) {
+                            item.tags.push(tag);
+                        }
+                    }
+                }
+                return Ok(item.to_added_json());
+            },
+            "tag_rename" => {
+                let (old_tag, new_tag) = match (find_str(action, "old_tag"), find_str(action, "new_tag")) {
+                    (Some(old_tag), Some(new_tag)) => (old_tag, new_tag),
+                    _ => return Err((400, "Bad Request", "Missing tag name.".to_string()))
+                };
+                for item in self.items.values_mut().filter(|item| item.tags.iter().any(|t| t == old_tag)) {
+                    item.tags.retain(|t| t != old_tag && t != new_tag);
+                    item.tags.push(new_tag.to_string());
+                    item.time_updated = now;
+                }
+                return Ok(Json::Boolean(true));
+            },
+            _ => ()
+        }
+
+        let item = match find_u64(action, "item_id").and_then(|id| self.items.get_mut(&id)) {
+            Some(item) => item,
+            None => return Err((404, "Not Found", "Item not found.".to_string()))
+        };
+
+        match name {
+            "archive" => {
+                item.status = PocketItemStatus::Archived;
+                item.time_read = now;
+            },
+            "readd" => {
+                item.status = PocketItemStatus::Normal;
+                item.time_read = 0;
+            },
+            "favorite" => {
+                item.favorite = true;
+                item.time_favorited = now;
+            },
+            "unfavorite" => {
+                item.favorite = false;
+                item.time_favorited = 0;
+            },
+            "delete" => item.status = PocketItemStatus::Deleted,
+            "tags_add" | "tags_replace" => {
+                let tags = match find_str(action, "tags") {
+                    Some(tags) => split_tags(tags),
+                    None => return Err((400, "Bad Request", "Missing tags.".to_string()))
+                };
+                if name == "tags_replace" {
+                    item.tags.clear();
+                }
+                for tag in tags {
+                    if !item.tags.contains(&tag) {
+                        item.tags.push(tag);
+                    }
+                }
+            },
+            "tags_clear" => item.tags.clear(),
+            _ => return Err((400, "Bad Request", format!("Invalid action: {}", name)))
+        }
+
+        item.time_updated = now;
+        Ok(Json::Boolean(true))
+    }
+}
+
+// Serves one request per connection, so nothing is left waiting on idle keep-alive connections
+fn handle(state: &Mutex<MockState>, mut stream: TcpStream) -> io::Result<()> {
+    try!(stream.set_read_timeout(Some(Duration::from_secs(5))));
+
+    let (uri, body) = {
+        let mut reader = BufReader::new(&stream);
+        let mut line = String::new();
+        try!(reader.read_line(&mut line));
+        let uri = line.split(' ').nth(1).unwrap_or("").to_string();
+
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            if try!(reader.read_line(&mut header)) == 0 || header.trim().is_empty() {
+                break;
+            }
+            let mut parts = header.splitn(2, ':');
+            if parts.next().map_or(false, |name| name.trim().eq_ignore_ascii_case("content-length")) {
+                length = parts.next().and_then(|v| v.trim().parse().ok()).unwrap_or(0);
+            }
+        }
+
+        let mut body = vec![0; length];
+        try!(reader.read_exact(&mut body));
+        (uri, String::from_utf8_lossy(&body).into_owned())
+    };
+
+    let (path, query) = match uri.find('?') {
+        Some(pos) => (&uri[..pos], &uri[pos + 1..]),
+        None => (&*uri, "")
+    };
+
+    let reply = {
+        let mut state = state.lock().unwrap();
+        match path {
+            "/auth/authorize" => state.authorize_page(query),
+            _ => state.api(path, &*body)
+        }
+    };
+
+    let (status, headers, body) = match reply {
+        Reply::Json(json) => (200, vec![("Content-Type", "application/json".to_string())], json.to_string()),
+        Reply::Redirect(location) => (302, vec![("Location", location)], String::new()),
+        Reply::Error(status, code, message) => (status, vec![("X-Error-Code", code.to_string()), ("X-Error", message)], String::new())
+    };
+
+    try!(write!(stream, "HTTP/1.1 {} {}\r\n", status, reason(status)));
+    for (name, value) in headers {
+        try!(write!(stream, "{}: {}\r\n", name, value));
+    }
+    try!(write!(stream, "Content-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body));
+    stream.flush()
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        302 => "Found",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        503 => "Service Unavailable",
+        _ => "Error"
+    }
+}
+
+// In-process stand-in for Pocket API, enabled with `mock` feature. It serves `/v3/oauth/request`,
+// `/v3/oauth/authorize`, `/v3/add`, `/v3/get`, `/v3/send` and the `/auth/authorize` page
+// from an in-memory item store, so applications can be tested end to end without network access.
+// Stops listening and frees the port when dropped.
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>
+}
+
+impl MockServer {
+    // Listens on a random local port, accepts given consumer key only
+    pub fn start(consumer_key: &str) -> PocketResult<MockServer> {
+        let state = Arc::new(Mutex::new(MockState {
+            consumer_key: consumer_key.to_string(),
+            access_token: "mock-access-token".to_string(),
+            username: "mock-user".to_string(),
+            request_tokens: BTreeMap::new(),
+            next_token: 0,
+            next_item_id: 1,
+            items: BTreeMap::new(),
+            failure: None
+        }));
+
+        let listener = try!(TcpListener::bind("127.0.0.1:0"));
+        let addr = try!(listener.local_addr());
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let state = state.clone();
+            let shutdown = shutdown.clone();
+            thread::spawn(move || for stream in listener.incoming() {
+                if shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let _ = handle(&state, stream);
+                }
+            })
+        };
+
+        Ok(MockServer {
+            addr: addr,
+            state: state,
+            shutdown: shutdown,
+            thread: Some(thread)
+        })
+    }
+
+    pub fn base_url(&self) -> Url {
+        Url::parse(&*format!("http://{}/v3/", self.addr)).unwrap()
+    }
+
+    pub fn authorize_url(&self) -> Url {
+        Url::parse(&*format!("http://{}/auth/authorize", self.addr)).unwrap()
+    }
+
+    // Client talking to this server
+    #[cfg(feature = "hyper")]
+    pub fn client(&self, access_token: Option<&str>) -> Pocket {
+        let consumer_key = self.state.lock().unwrap().consumer_key.clone();
+        Pocket::with_urls(&*consumer_key, access_token, self.base_url(), self.authorize_url())
+    }
+
+    #[cfg(feature = "async")]
+    pub fn async_client(&self, access_token: Option<&str>) -> PocketAsync {
+        let consumer_key = self.state.lock().unwrap().consumer_key.clone();
+        PocketAsync::with_urls(&*consumer_key, access_token, self.base_url(), self.authorize_url())
+    }
+
+    pub fn access_token(&self) -> String {
+        self.state.lock().unwrap().access_token.clone()
+    }
+
+    pub fn username(&self) -> String {
+        self.state.lock().unwrap().username.clone()
+    }
+
+    // Does the same as a user confirming access on authorization page
+    pub fn approve(&self, request_token: &str) -> bool {
+        match self.state.lock().unwrap().request_tokens.get_mut(request_token) {
+            Some(token) => {
+                token.approved = true;
+                true
+            },
+            None => false
+        }
+    }
+
+    pub fn insert(&self, url: &str, title: &str) -> u64 {
+        self.state.lock().unwrap().insert(url, title, get_time().sec)
+    }
+
+    pub fn update<F: FnOnce(&mut MockItem)>(&self, item_id: u64, f: F) -> bool {
+        self.state.lock().unwrap().items.get_mut(&item_id).map(f).is_some()
+    }
+
+    pub fn item(&self, item_id: u64) -> Option<MockItem> {
+        self.state.lock().unwrap().items.get(&item_id).cloned()
+    }
+
+    pub fn items(&self) -> Vec<MockItem> {
+        self.state.lock().unwrap().items.values().cloned().collect()
+    }
+
+    // Next API call fails with given HTTP status and X-Error-Code/X-Error headers
+    pub fn fail_next(&self, status: u16, code: u16, message: &str) {
+        self.state.lock().unwrap().failure = Some((status, code, message.to_string()));
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // wake up accept loop, so it sees the flag
+        let _ = TcpStream::connect(self.addr);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
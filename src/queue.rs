@@ -0,0 +1,177 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use rustc_serialize::{Encodable, Encoder};
+use rustc_serialize::json::{self, Json};
+use time;
+
+use super::{Pocket, PocketResult, PocketError, PocketAction, PocketActionResult};
+use store::atomic_write;
+
+const BATCH_SIZE: usize = 100;
+
+// Action stored in the queue in its `/v3/send` form
+struct QueuedAction {
+    name: &'static str,
+    item_id: Option<u64>,
+    action: Json
+}
+
+impl QueuedAction {
+    fn from_json(action: Json) -> QueuedAction {
+        QueuedAction {
+            name: action_name(action.find("name").and_then(|v| v.as_string()).unwrap_or("")),
+            item_id: action.find("item_id").and_then(|v| v.as_u64()),
+            action: action
+        }
+    }
+}
+
+impl PocketAction for QueuedAction {
+    fn name(&self) -> &'static str { self.name }
+    fn item_id(&self) -> Option<u64> { self.item_id }
+}
+
+impl Encodable for QueuedAction {
+    fn encode<S: Encoder>(&self, e: &mut S) -> Result<(), S::Error> {
+        self.action.encode(e)
+    }
+}
+
+// Pocket reports server issues (5xx) with code 199 and authentication failures (401/403)
+// with codes 107 and 152, any other code means the request itself was rejected (4xx)
+fn is_rejected(e: &PocketError) -> bool {
+    match *e {
+        PocketError::Proto(code, _) => code != 199 && code != 107 && code != 152,
+        _ => false
+    }
+}
+
+fn action_name(name: &str) -> &'static str {
+    match name {
+        "add" => "add",
+        "archive" => "archive",
+        "readd" => "readd",
+        "favorite" => "favorite",
+        "unfavorite" => "unfavorite",
+        "delete" => "delete",
+        "tags_add" => "tags_add",
+        "tags_replace" => "tags_replace",
+        "tags_clear" => "tags_clear",
+        "tag_rename" => "tag_rename",
+        _ => "unknown"
+    }
+}
+
+// Durable queue of actions to send later, e.g. made while offline. Actions are stored
+// in JSON lines file, one action per line, with action time set when it's queued,
+// so the server applies them as of the time they were made, not when they are sent.
+pub struct PocketActionQueue {
+    path: PathBuf,
+    actions: VecDeque<QueuedAction>
+}
+
+impl PocketActionQueue {
+    // Empty queue is created if there's no file yet
+    pub fn open<P: AsRef<Path>>(path: P) -> PocketResult<PocketActionQueue> {
+        let mut queue = PocketActionQueue {
+            path: path.as_ref().to_path_buf(),
+            actions: VecDeque::new()
+        };
+
+        let file = match File::open(&queue.path) {
+            Ok(file) => file,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(queue),
+            Err(e) => return Err(From::from(e))
+        };
+
+        for line in BufReader::new(file).lines() {
+            let line = try!(line);
+            if !line.trim().is_empty() {
+                queue.actions.push_back(QueuedAction::from_json(try!(Json::from_str(&*line))));
+            }
+        }
+        Ok(queue)
+    }
+
+    // Action is stored before this method returns
+    pub fn push(&mut self, action: &PocketAction) -> PocketResult<()> {
+        let mut encoded = String::new();
+        {
+            let mut encoder = json::Encoder::new(&mut encoded);
+            try!(action.json_encode(&mut encoder));
+        }
+
+        let mut action = try!(Json::from_str(&*encoded));
+        if let Json::Object(ref mut obj) = action {
+            if obj.get("time").map_or(true, |v| v.is_null()) {
+                obj.insert("time".to_string(), Json::U64(time::get_time().sec as u64));
+            }
+        }
+
+        self.actions.push_back(QueuedAction::from_json(action));
+        self.save()
+    }
+
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    pub fn clear(&mut self) -> PocketResult<()> {
+        self.actions.clear();
+        self.save()
+    }
+
+    pub fn save(&self) -> PocketResult<()> {
+        atomic_write(&self.path, |out| {
+            for action in &self.actions {
+                try!(writeln!(out, "{}", action.action));
+            }
+            Ok(())
+        })
+    }
+
+    // Sends next batch of actions. Sent actions are removed from the queue, including
+    // the ones rejected by the server, which are reported with `success == false` results.
+    // If the request fails because of network, server issue or authentication, the batch
+    // stays in the queue to be sent again. If the whole request is rejected, sending it again
+    // won't help, so the batch is removed and the error is returned.
+    pub fn flush_batch(&mut self, pocket: &mut Pocket) -> PocketResult<Vec<PocketActionResult>> {
+        let count = ::std::cmp::min(self.actions.len(), BATCH_SIZE);
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+
+        let results = {
+            let batch: Vec<&PocketAction> = self.actions.iter().take(count).map(|a| a as &PocketAction).collect();
+            pocket.send(&*batch)
+        };
+
+        match results {
+            Err(ref e) if !is_rejected(e) => (),
+            _ => {
+                self.actions.drain(..count);
+                try!(self.save());
+            }
+        }
+        results
+    }
+
+    // Sends all queued actions in batches, results are in queue order. Stops at the first failed
+    // batch and returns its error along with results of batches sent before it.
+    pub fn flush(&mut self, pocket: &mut Pocket) -> (Vec<PocketActionResult>, Option<PocketError>) {
+        let mut results = Vec::new();
+        while !self.actions.is_empty() {
+            match self.flush_batch(pocket) {
+                Ok(batch) => results.extend(batch),
+                Err(e) => return (results, Some(e))
+            }
+        }
+        (results, None)
+    }
+}
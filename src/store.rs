@@ -0,0 +1,27 @@
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use super::PocketResult;
+
+// Writes file contents to a temporary file next to it, then renames it over the file,
+// so failed write doesn't corrupt it
+pub fn atomic_write<F>(path: &Path, write: F) -> PocketResult<()>
+    where F: FnOnce(&mut Write) -> PocketResult<()> {
+    let tmp_path = tmp_path(path);
+    {
+        let mut out = BufWriter::new(try!(File::create(&tmp_path)));
+        try!(write(&mut out));
+        try!(out.flush());
+    }
+    try!(fs::rename(&tmp_path, path));
+    Ok(())
+}
+
+// ".tmp" is appended to full file name, so files differing only by extension don't share it
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().map(|v| v.to_os_string()).unwrap_or_else(OsString::new);
+    name.push(".tmp");
+    path.with_file_name(name)
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+use time::Timespec;
+
+use super::{Pocket, PocketResult, PocketItem, PocketItemStatus, PocketGetState, PocketGetDetail, PocketDecodeError};
+
+const PAGE_SIZE: usize = 500;
+
+// Incremental sync state, keeps server time of the last sync,
+// so only items changed after it are requested next time.
+// It's `Encodable`, so it can be stored between application runs.
+#[derive(RustcEncodable, RustcDecodable, Debug, Clone, PartialEq)]
+pub struct PocketSync {
+    since: Option<i64>
+}
+
+// Items changed since the last sync, deleted items are given by item_id only.
+// Changed items failed to decode are reported in `errors` and are not fetched again.
+#[derive(Debug, PartialEq)]
+pub struct PocketChanges {
+    pub added: Vec<PocketItem>,
+    pub updated: Vec<PocketItem>,
+    pub archived: Vec<PocketItem>,
+    pub deleted: Vec<u64>,
+    pub errors: Vec<PocketDecodeError>
+}
+
+impl PocketChanges {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.updated.is_empty() && self.archived.is_empty() && self.deleted.is_empty() &&
+            self.errors.is_empty()
+    }
+}
+
+impl PocketSync {
+    // First sync will fetch all items
+    pub fn new() -> PocketSync {
+        PocketSync {
+            since: None
+        }
+    }
+
+    pub fn from_since(since: Timespec) -> PocketSync {
+        PocketSync {
+            since: Some(since.sec)
+        }
+    }
+
+    pub fn since(&self) -> Option<Timespec> {
+        self.since.map(|v| Timespec::new(v, 0))
+    }
+
+    // Fetches all items changed since the last sync with complete details, state is updated
+    // only if all pages were fetched, so failed sync can be just repeated
+    pub fn sync(&mut self, pocket: &mut Pocket) -> PocketResult<PocketChanges> {
+        // deleted items are kept as `None`, as they come as stubs which can't be decoded as items
+        let mut items = BTreeMap::new();
+        let mut errors = Vec::new();
+        let mut next_since = None;
+
+        let mut request = pocket.filter();
+        request.state(PocketGetState::All).detail_type(PocketGetDetail::Complete);
+        if let Some(since) = self.since() {
+            request.since(since);
+        }
+
+        let mut pages = request.pages(PAGE_SIZE);
+        while let Some(response) = pages.next_response() {
+            let response = try!(response);
+
+            // changes made while paging will be fetched with the next sync
+            if next_since.is_none() {
+                next_since = Some(response.since);
+            }
+
+            for item in response.list {
+                items.insert(item.item_id, Some(item));
+            }
+            for item_id in response.deleted {
+                items.insert(item_id, None);
+            }
+            errors.extend(response.errors);
+        }
+
+        let mut changes = self.classify(items);
+        changes.errors = errors;
+        self.since = next_since.map(|v| v.sec);
+        Ok(changes)
+    }
+
+    fn classify(&self, items: BTreeMap<u64, Option<PocketItem>>) -> PocketChanges {
+        let mut changes = PocketChanges {
+            added: Vec::new(),
+            updated: Vec::new(),
+            archived: Vec::new(),
+            deleted: Vec::new(),
+            errors: Vec::new()
+        };
+        let since = self.since();
+
+        for (item_id, item) in items {
+            let item = match item {
+                Some(item) => item,
+                None => {
+                    changes.deleted.push(item_id);
+                    continue;
+                }
+            };
+            match item.status {
+                PocketItemStatus::Deleted => changes.deleted.push(item.item_id),
+                PocketItemStatus::Archived => changes.archived.push(item),
+                _ => match since {
+                    Some(since) if item.time_added.map_or(false, |t| t < since) => changes.updated.push(item),
+                    _ => changes.added.push(item)
+                }
+            }
+        }
+        changes
+    }
+}
@@ -0,0 +1,95 @@
+use url::Url;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+use super::{PocketError, PocketResult};
+
+// Sends JSON encoded request to API method URL and returns response body,
+// failing with `PocketError::Proto` if server reported an error with X-Error-Code header
+pub trait PocketTransport {
+    fn post(&mut self, url: &Url, data: &str) -> PocketResult<String>;
+}
+
+#[derive(RustcEncodable, RustcDecodable, Debug, Clone, PartialEq)]
+pub struct PocketExchange {
+    pub url: String,
+    pub request: String,
+    pub response: String,
+    pub error_code: Option<u16>,
+    pub error: Option<String>
+}
+
+// Passes requests to inner transport and logs them along with responses and protocol errors,
+// so they can be stored (exchanges are `Encodable`) and played back with `ReplayTransport`
+pub struct RecordingTransport<T: PocketTransport> {
+    inner: T,
+    log: Arc<Mutex<Vec<PocketExchange>>>
+}
+
+impl<T: PocketTransport> RecordingTransport<T> {
+    pub fn new(inner: T) -> RecordingTransport<T> {
+        RecordingTransport {
+            inner: inner,
+            log: Arc::new(Mutex::new(Vec::new()))
+        }
+    }
+
+    // Shared handle to the log, keep it to read exchanges after the transport is moved into `Pocket`
+    pub fn log(&self) -> Arc<Mutex<Vec<PocketExchange>>> {
+        self.log.clone()
+    }
+}
+
+impl<T: PocketTransport> PocketTransport for RecordingTransport<T> {
+    fn post(&mut self, url: &Url, data: &str) -> PocketResult<String> {
+        let result = self.inner.post(url, data);
+        let (response, error_code, error) = match result {
+            Ok(ref body) => (body.clone(), None, None),
+            Err(PocketError::Proto(code, ref msg)) => (String::new(), Some(code), Some(msg.clone())),
+            Err(_) => return result
+        };
+
+        self.log.lock().unwrap().push(PocketExchange {
+            url: url.to_string(),
+            request: data.to_string(),
+            response: response,
+            error_code: error_code,
+            error: error
+        });
+        result
+    }
+}
+
+// Answers requests with recorded exchanges in order, failing on any request not matching the record
+pub struct ReplayTransport {
+    exchanges: VecDeque<PocketExchange>
+}
+
+impl ReplayTransport {
+    pub fn new(exchanges: Vec<PocketExchange>) -> ReplayTransport {
+        ReplayTransport {
+            exchanges: exchanges.into_iter().collect()
+        }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.exchanges.len()
+    }
+}
+
+impl PocketTransport for ReplayTransport {
+    fn post(&mut self, url: &Url, data: &str) -> PocketResult<String> {
+        let url = url.to_string();
+        match self.exchanges.pop_front() {
+            Some(ref exchange) if exchange.url == url && exchange.request == data => match exchange.error_code {
+                None => Ok(exchange.response.clone()),
+                Some(code) => Err(PocketError::Proto(code, exchange.error.clone().unwrap_or_else(String::new)))
+            },
+            Some(exchange) => Err(From::from(io::Error::new(io::ErrorKind::Other,
+                format!("unexpected request to {}, expected request to {}", url, exchange.url)))),
+            None => Err(From::from(io::Error::new(io::ErrorKind::Other,
+                format!("unexpected request to {}, no more recorded exchanges", url))))
+        }
+    }
+}
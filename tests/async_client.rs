@@ -0,0 +1,81 @@
+extern crate pocket;
+extern crate futures;
+extern crate tokio;
+
+use pocket::{PocketArchiveAction, PocketTagsAddAction, PocketError};
+use pocket::mock::MockServer;
+use tokio::runtime::Runtime;
+
+#[test]
+fn test_async_oauth() {
+    let server = MockServer::start("consumer").unwrap();
+    let pocket = server.async_client(None);
+    let rt = Runtime::new().unwrap();
+
+    match rt.block_on(pocket.authorize()) {
+        Err(PocketError::MissingRequestToken) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+
+    rt.block_on(pocket.get_auth_url()).unwrap();
+    assert!(server.approve(&*pocket.pending_token().unwrap().code));
+
+    // clones share tokens
+    let username = rt.block_on(pocket.clone().authorize()).unwrap();
+    assert_eq!(username, server.username());
+    assert_eq!(pocket.access_token(), Some(server.access_token()));
+    assert_eq!(pocket.pending_token(), None);
+}
+
+#[test]
+fn test_async_add_get_send() {
+    let server = MockServer::start("consumer").unwrap();
+    let pocket = server.async_client(Some(&*server.access_token()));
+    let rt = Runtime::new().unwrap();
+
+    // shared between tasks running on the runtime at the same time
+    let tasks: Vec<_> = (0..4).map(|i| rt.spawn(pocket.add(&*format!("http://example.com/{}", i), Some("Example"), None, None))).collect();
+    let mut ids: Vec<u64> = rt.block_on(futures::future::join_all(tasks)).into_iter()
+        .map(|r| r.unwrap().unwrap().item_id).collect();
+    ids.sort();
+    assert_eq!(server.items().len(), 4);
+
+    let tag = "rust".to_string();
+    let send = pocket.send(&[&PocketArchiveAction::new(ids[0]), &PocketTagsAddAction::new(ids[1], &*tag)]);
+    drop(tag); // actions are already encoded
+    let results = rt.block_on(send).unwrap();
+    assert!(results.iter().all(|r| r.success));
+    assert_eq!(server.item(ids[1]).unwrap().tags, vec!["rust".to_string()]);
+
+    let archived = {
+        let mut f = pocket.filter();
+        f.archived();
+        f.get()
+    };
+    let unread = pocket.filter().get();
+    let (archived, unread) = rt.block_on(futures::future::join(archived, unread));
+    assert_eq!(archived.unwrap().iter().map(|v| v.item_id).collect::<Vec<_>>(), vec![ids[0]]);
+    assert_eq!(unread.unwrap().len(), 3);
+
+    match rt.block_on(pocket.push("not a url")) {
+        Err(PocketError::InvalidUrl(_)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
+
+#[test]
+fn test_async_errors() {
+    let server = MockServer::start("consumer").unwrap();
+    let rt = Runtime::new().unwrap();
+
+    match rt.block_on(server.async_client(None).filter().get()) {
+        Err(PocketError::NotAuthorized) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+
+    server.fail_next(503, 199, "Pocket server issue.");
+    match rt.block_on(server.async_client(Some(&*server.access_token())).push("http://example.com/")) {
+        Err(PocketError::Proto(199, ref message)) if message == "Pocket server issue." => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
@@ -0,0 +1,432 @@
+extern crate pocket;
+extern crate rustc_serialize;
+extern crate hyper;
+extern crate time;
+
+use pocket::{Pocket, PocketError, PocketRequestToken, PocketSync, PocketCache, PocketActionQueue, PocketFavoriteAction, PocketGetState, PocketGetTag, HyperTransport, RecordingTransport, ReplayTransport, PocketArchiveAction, PocketTagsAddAction, PocketDeleteAction};
+use pocket::mock::MockServer;
+use rustc_serialize::json;
+use hyper::client::{Client, RedirectPolicy};
+use hyper::header::Location;
+use std::thread;
+use std::{env, fs};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+
+fn request_token(pocket: &mut Pocket) -> String {
+    let url = pocket.get_auth_url().unwrap();
+    url.query_pairs().unwrap().into_iter()
+        .find(|&(ref k, _)| k == "request_token")
+        .map(|(_, v)| v)
+        .unwrap()
+}
+
+#[test]
+fn test_mock_oauth() {
+    let server = MockServer::start("consumer").unwrap();
+    let mut pocket = server.client(None);
+
+    request_token(&mut pocket);
+    match pocket.authorize() {
+        Err(PocketError::Proto(158, _)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+
+    let token = request_token(&mut pocket);
+    assert!(server.approve(&*token));
+    assert_eq!(pocket.authorize().unwrap(), server.username());
+    assert_eq!(pocket.access_token(), Some(&*server.access_token()));
+}
+
+#[test]
+fn test_mock_add_get_send() {
+    let server = MockServer::start("consumer").unwrap();
+    let mut pocket = server.client(Some(&*server.access_token()));
+
+    let added = pocket.push("http://example.com/").unwrap();
+    let other_id = server.insert("http://example.org/rust", "Rust");
+
+    let items = pocket.filter().get().unwrap();
+    assert_eq!(items.len(), 2);
+
+    let results = pocket.send(&[
+        &PocketArchiveAction::new(added.item_id),
+        &PocketTagsAddAction::new(other_id, "lang,rust"),
+        &PocketDeleteAction::new(12345)
+    ]).unwrap();
+    assert_eq!(results.iter().map(|r| r.success).collect::<Vec<_>>(), vec![true, true, false]);
+    assert!(results[2].error.is_some());
+
+    let items = {
+        let mut f = pocket.filter();
+        f.archived();
+        f.get().unwrap()
+    };
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].item_id, added.item_id);
+    assert_eq!(server.item(other_id).unwrap().tags, vec!["lang".to_string(), "rust".to_string()]);
+
+    let items = pocket.filter().get().unwrap();
+    assert_eq!(items[0].item_id, other_id);
+    assert_eq!(items[0].tags.iter().map(|t| &*t.tag).collect::<Vec<_>>(), vec!["lang", "rust"]);
+}
+
+#[test]
+fn test_mock_errors() {
+    let server = MockServer::start("consumer").unwrap();
+
+    let mut pocket = server.client(Some("wrong-token"));
+    match pocket.push("http://example.com/") {
+        Err(PocketError::Proto(107, _)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+
+    let mut pocket = server.client(Some(&*server.access_token()));
+    server.fail_next(503, 199, "Pocket server issue.");
+    match pocket.filter().get() {
+        Err(PocketError::Proto(199, ref msg)) if msg == "Pocket server issue." => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+    assert!(pocket.filter().get().is_ok());
+}
+
+#[test]
+fn test_mock_record_replay() {
+    let server = MockServer::start("consumer").unwrap();
+    let mut pocket = server.client(Some(&*server.access_token()));
+    let recorder = RecordingTransport::new(HyperTransport::new());
+    let log = recorder.log();
+    pocket.set_transport(recorder);
+
+    let added = pocket.push("http://example.com/").unwrap();
+    let items = pocket.filter().get().unwrap();
+    server.fail_next(503, 199, "Pocket server issue.");
+    assert!(pocket.filter().get().is_err());
+
+    let exchanges = log.lock().unwrap().clone();
+    assert_eq!(exchanges.len(), 3);
+    assert_eq!(exchanges[2].error_code, Some(199));
+
+    let mut pocket = server.client(Some(&*server.access_token()));
+    pocket.set_transport(ReplayTransport::new(exchanges));
+    assert_eq!(pocket.push("http://example.com/").unwrap(), added);
+    assert_eq!(pocket.filter().get().unwrap(), items);
+    match pocket.filter().get() {
+        Err(PocketError::Proto(199, _)) => (),
+        other => panic!("unexpected result: {:?}", other)
+    }
+}
+
+#[test]
+fn test_mock_oauth_redirect_state() {
+    let server = MockServer::start("consumer").unwrap();
+    let mut pocket = server.client(None);
+
+    let url = pocket.get_auth_url_with("http://127.0.0.1:9999/callback", Some("csrf-token")).unwrap();
+    let params = url.query_pairs().unwrap();
+    assert!(params.contains(&("redirect_uri".to_string(), "http://127.0.0.1:9999/callback".to_string())));
+    assert_eq!(pocket.auth_state(), Some("csrf-token"));
+
+    let token = params.into_iter().find(|&(ref k, _)| k == "request_token").unwrap().1;
+    assert!(server.approve(&*token));
+    assert_eq!(pocket.authorize().unwrap(), server.username());
+}
+
+#[test]
+fn test_mock_oauth_stored_token() {
+    let server = MockServer::start("consumer").unwrap();
+
+    let stored = {
+        let mut pocket = server.client(None);
+        let token = pocket.request_token("http://127.0.0.1:9999/callback", Some("csrf-token")).unwrap();
+        assert_eq!(pocket.auth_url(&token).query_pairs().unwrap()[0], ("request_token".to_string(), token.code.clone()));
+        json::encode(&token).unwrap()
+    };
+
+    let token: PocketRequestToken = json::decode(&*stored).unwrap();
+    assert!(server.approve(&*token.code));
+
+    let mut pocket = server.client(None);
+    assert_eq!(pocket.authorize_token(&token).unwrap(), server.username());
+    assert_eq!(pocket.access_token(), Some(&*server.access_token()));
+}
+
+#[test]
+fn test_mock_oauth_loopback() {
+    let server = MockServer::start("consumer").unwrap();
+    let mut pocket = server.client(None);
+
+    let (username, access_token) = pocket.authorize_loopback(Duration::from_secs(30), |url| {
+        let url = url.clone();
+        thread::spawn(move || {
+            let mut client = Client::new();
+            client.set_redirect_policy(RedirectPolicy::FollowNone);
+            let res = client.get(url).send().unwrap();
+            let callback = res.headers.get::<Location>().unwrap().0.clone();
+            let favicon = callback.split('?').next().unwrap().replace("/pocket/callback", "/favicon.ico");
+            client.get(&*favicon).send().unwrap();
+            client.get(&*format!("{}&code=1", callback)).send().unwrap();
+        });
+    }).unwrap();
+
+    assert_eq!(username, server.username());
+    assert_eq!(access_token, server.access_token());
+
+    // user never comes back
+    let started = Instant::now();
+    assert!(pocket.authorize_loopback(Duration::from_millis(200), |_| ()).is_err());
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_mock_get_order() {
+    let server = MockServer::start("consumer").unwrap();
+    let mut pocket = server.client(Some(&*server.access_token()));
+    let ids = vec![
+        server.insert("http://example.com/b", "B"),
+        server.insert("http://example.com/c", "C"),
+        server.insert("http://example.com/a", "A")
+    ];
+
+    let titles = |items: Vec<pocket::PocketItem>| items.into_iter().map(|v| v.given_title).collect::<Vec<_>>();
+    let items = {
+        let mut f = pocket.filter();
+        f.sort_by_title();
+        f.get().unwrap()
+    };
+    assert_eq!(titles(items), vec!["A", "B", "C"]);
+
+    let items = {
+        let mut f = pocket.filter();
+        f.sort_by_newest();
+        f.get().unwrap()
+    };
+    assert_eq!(titles(items), vec!["A", "C", "B"]);
+
+    let items = pocket.filter().get_map().unwrap();
+    assert_eq!(items.keys().cloned().collect::<Vec<_>>(), ids);
+    assert_eq!(items[&ids[0]].given_title, "B");
+}
+
+#[test]
+fn test_mock_get_response() {
+    let server = MockServer::start("consumer").unwrap();
+    let mut pocket = server.client(Some(&*server.access_token()));
+    for i in 0..3 {
+        server.insert(&*format!("http://example.com/{}", i), "Example");
+    }
+
+    let response = {
+        let mut f = pocket.filter();
+        f.slice(0, 2);
+        f.get_response().unwrap()
+    };
+    assert_eq!(response.list.len(), 2);
+    assert_eq!((response.status, response.complete, response.error), (1, true, None));
+    assert!(response.since.sec > 0);
+    assert!(response.errors.is_empty());
+
+    let meta = response.search_meta.unwrap();
+    assert_eq!((meta.total_result_count, meta.has_more), (Some(3), Some(true)));
+}
+
+#[test]
+fn test_mock_get_pages() {
+    let server = MockServer::start("consumer").unwrap();
+    let mut pocket = server.client(Some(&*server.access_token()));
+    for i in 0..7 {
+        server.insert(&*format!("http://example.com/{}", i), "Example");
+    }
+
+    let sizes = pocket.filter().pages(3).map(|page| page.unwrap().list.len()).collect::<Vec<_>>();
+    assert_eq!(sizes, vec![3, 3, 1]);
+
+    let sizes = {
+        let mut f = pocket.filter();
+        f.offset(1);
+        f.pages(2).map(|page| page.unwrap().list.len()).collect::<Vec<_>>()
+    };
+    assert_eq!(sizes, vec![2, 2, 2]);
+
+    let items = pocket.filter().pages(5).items().collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!(items.len(), 7);
+
+    server.fail_next(503, 199, "Pocket server issue.");
+    let mut pages = pocket.filter().pages(3);
+    match pages.next() {
+        Some(Err(PocketError::Proto(199, _))) => (),
+        other => panic!("unexpected result: {:?}", other.map(|v| v.map(|v| v.list.len())))
+    }
+    assert!(pages.next().is_none());
+}
+
+#[test]
+fn test_mock_sync() {
+    let server = MockServer::start("consumer").unwrap();
+    let mut pocket = server.client(Some(&*server.access_token()));
+    let ids = (0..4).map(|i| server.insert(&*format!("http://example.com/{}", i), "Example")).collect::<Vec<_>>();
+    for &id in &ids {
+        server.update(id, |item| {
+            item.time_added -= 3600;
+            item.time_updated -= 3600;
+        });
+    }
+
+    let mut sync = PocketSync::new();
+    let changes = sync.sync(&mut pocket).unwrap();
+    assert_eq!(changes.added.len(), 4);
+    assert!(changes.updated.is_empty() && changes.archived.is_empty() && changes.deleted.is_empty());
+    assert!(sync.since().is_some());
+
+    // restore the state as if it was stored between runs
+    let mut sync: PocketSync = json::decode(&*json::encode(&sync).unwrap()).unwrap();
+    assert!(sync.sync(&mut pocket).unwrap().is_empty());
+
+    let new_id = pocket.push("http://example.com/new").unwrap().item_id;
+    pocket.send(&[
+        &PocketArchiveAction::new(ids[0]),
+        &PocketDeleteAction::new(ids[1]),
+        &PocketTagsAddAction::new(ids[2], "rust")
+    ]).unwrap();
+
+    let changes = sync.sync(&mut pocket).unwrap();
+    assert_eq!(changes.added.iter().map(|v| v.item_id).collect::<Vec<_>>(), vec![new_id]);
+    assert_eq!(changes.updated.iter().map(|v| v.item_id).collect::<Vec<_>>(), vec![ids[2]]);
+    assert_eq!(changes.archived.iter().map(|v| v.item_id).collect::<Vec<_>>(), vec![ids[0]]);
+    assert_eq!(changes.deleted, vec![ids[1]]);
+    assert!(changes.errors.is_empty());
+
+    // a broken item is reported, and doesn't stop other changes from syncing
+    let since = sync.since().unwrap();
+    pocket.send(&[&PocketArchiveAction::new(ids[2])]).unwrap();
+    server.update(ids[3], |item| {
+        item.url = "not a url".to_string();
+        item.time_updated = since.sec;
+    });
+    let changes = sync.sync(&mut pocket).unwrap();
+    // items changed within the second of the last sync are fetched again
+    assert!(changes.archived.iter().any(|v| v.item_id == ids[2]));
+    assert_eq!(changes.errors.iter().map(|e| e.item_id.clone()).collect::<Vec<_>>(), vec![ids[3].to_string()]);
+    assert!(sync.since().unwrap() >= since);
+}
+
+#[test]
+fn test_mock_cache() {
+    let server = MockServer::start("consumer").unwrap();
+    let mut pocket = server.client(Some(&*server.access_token()));
+    let rust = server.insert("http://www.rust-lang.org/", "Rust");
+    let example = server.insert("http://example.com/", "Example");
+    let archived = server.insert("http://example.org/archived", "Archived");
+    pocket.send(&[&PocketArchiveAction::new(archived), &PocketTagsAddAction::new(rust, "lang")]).unwrap();
+
+    let path = env::temp_dir().join(format!("pocket-cache-test-{}.jsonl", server.base_url().port().unwrap()));
+    let _ = fs::remove_file(&path);
+
+    let mut cache = PocketCache::open(&path).unwrap();
+    assert!(cache.is_empty());
+    cache.sync(&mut pocket).unwrap();
+    assert_eq!(cache.len(), 3);
+
+    pocket.send(&[&PocketDeleteAction::new(example)]).unwrap();
+    let changes = cache.sync(&mut pocket).unwrap();
+    assert_eq!(changes.deleted, vec![example]);
+
+    // reopen and query without network
+    let cache = PocketCache::open(&path).unwrap();
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.sync_state().since().is_some(), true);
+    let mut offline = Pocket::new("consumer", None);
+    let ids = |items: Vec<&pocket::PocketItem>| items.iter().map(|v| v.item_id).collect::<Vec<_>>();
+
+    assert_eq!(ids(cache.get(&offline.filter())), vec![rust]);
+    {
+        let mut f = offline.filter();
+        f.archived();
+        assert_eq!(ids(cache.get(&f)), vec![archived]);
+    }
+    {
+        let mut f = offline.filter();
+        f.state(PocketGetState::All).domain("example.org");
+        assert_eq!(ids(cache.get(&f)), vec![archived]);
+    }
+    {
+        let mut f = offline.filter();
+        f.state(PocketGetState::All).tag(PocketGetTag::Tagged("lang")).search("rust");
+        assert_eq!(ids(cache.get(&f)), vec![rust]);
+    }
+    {
+        let mut f = offline.filter();
+        f.state(PocketGetState::All).sort_by_title().slice(1, 1);
+        assert_eq!(ids(cache.get(&f)), vec![rust]);
+    }
+    assert_eq!(cache.item(rust).unwrap().tags[0].tag, "lang");
+    assert!(cache.item(example).is_none());
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_mock_action_queue() {
+    let server = MockServer::start("consumer").unwrap();
+    let mut pocket = server.client(Some(&*server.access_token()));
+    let item_id = server.insert("http://www.rust-lang.org/", "Rust");
+
+    let path = env::temp_dir().join(format!("pocket-queue-test-{}.jsonl", server.base_url().port().unwrap()));
+    let _ = fs::remove_file(&path);
+
+    let queued_at = time::get_time().sec;
+    {
+        let mut queue = PocketActionQueue::open(&path).unwrap();
+        queue.push(&PocketFavoriteAction::new(item_id)).unwrap();
+        queue.push(&PocketTagsAddAction::new(item_id, "lang")).unwrap();
+        queue.push(&PocketArchiveAction::new(item_id).at(time::Timespec::new(1000, 0))).unwrap();
+        queue.push(&PocketDeleteAction::new(12345)).unwrap();
+    }
+
+    // restart while offline, then go online
+    let mut queue = PocketActionQueue::open(&path).unwrap();
+    assert_eq!(queue.len(), 4);
+
+    server.fail_next(503, 199, "Pocket server issue.");
+    match queue.flush(&mut pocket) {
+        (ref results, Some(PocketError::Proto(199, _))) if results.is_empty() => (),
+        (results, error) => panic!("unexpected result: {} results, {:?}", results.len(), error)
+    }
+    assert_eq!(queue.len(), 4);
+
+    server.fail_next(401, 107, "Consumer key/access token mismatch.");
+    assert!(queue.flush_batch(&mut pocket).is_err());
+    assert_eq!(queue.len(), 4);
+
+    let (results, error) = queue.flush(&mut pocket);
+    assert!(error.is_none());
+    assert_eq!(results.iter().map(|r| (r.name, r.success)).collect::<Vec<_>>(),
+               vec![("favorite", true), ("tags_add", true), ("archive", true), ("delete", false)]);
+    assert_eq!(results[3].item_id, Some(12345));
+    assert!(queue.is_empty());
+    assert!(PocketActionQueue::open(&path).unwrap().is_empty());
+
+    let item = server.item(item_id).unwrap();
+    assert!(item.favorite && item.time_favorited >= queued_at && item.time_favorited <= time::get_time().sec);
+    assert_eq!(item.tags, vec!["lang".to_string()]);
+    assert_eq!(item.time_read, 1000);
+
+    // rejected batch won't be accepted later, so it's dropped
+    queue.push(&PocketFavoriteAction::new(item_id)).unwrap();
+    server.fail_next(400, 0, "Invalid request.");
+    match queue.flush(&mut pocket) {
+        (ref results, Some(PocketError::Proto(0, _))) if results.is_empty() => (),
+        (results, error) => panic!("unexpected result: {} results, {:?}", results.len(), error)
+    }
+    assert!(queue.is_empty());
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_mock_shutdown() {
+    let server = MockServer::start("consumer").unwrap();
+    let port = server.base_url().port().unwrap();
+    drop(server);
+    assert!(TcpStream::connect(("127.0.0.1", port)).is_err());
+}